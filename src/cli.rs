@@ -0,0 +1,187 @@
+/*
+    Headless mode, encoding a file from the command line according to a recipe:
+
+    bitcoder encode <input file> <recipe file> <output file> [--outcome <variable>]
+
+    The same is done by the console program bitcoder-cli (see console), which takes the same
+    arguments. On Windows only that one shows diagnostics, as the application has no console there.
+
+    The recipe is a list of variable settings (cards) in RON notation. A card belongs to the variable
+    of its name (as in the header of the input file), or else of its title, or else to the variable
+    at its position (not counting the outcome variable, which is the last one unless given with
+    --outcome). Fields left out get their default value, and variables without a card are recoded
+    as they are:
+
+    [
+        (title: "Age", is_numeric: true, expression: "low to 18, 18 to 65, 65 to max"),
+        (is_included: false),
+        (name: "Color", order: Frequency),
+    ]
+
+    A project file (saved from the user interface) can be used as recipe as well, and then the
+    dialect and outcome variable are taken from the project instead of being guessed.
+
+    Diagnostics are written to stderr and the exit code tells the outcome (see below).
+*/
+
+use crate::models::card::Card;
+use crate::models::dialect::Dialect;
+use crate::models::decoder::{
+    Decoder,
+    LoadReport
+};
+use crate::models::encoder::Encoder;
+use crate::models::project::{
+    self,
+    Project
+};
+use crate::models::variable::Variable;
+
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_USAGE:   i32 = 2; // Wrong arguments.
+const EXIT_INPUT:   i32 = 3; // Input file could not be read or decoded.
+const EXIT_RECIPE:  i32 = 4; // Recipe could not be read or does not match the input file.
+const EXIT_OUTPUT:  i32 = 5; // Output file could not be written.
+
+const USAGE: &str = "Usage: bitcoder encode <input file> <recipe file> <output file> [--outcome <variable>]";
+
+pub fn run (arguments: &[String]) -> i32 {
+    match arguments {
+        [command, input, recipe, output] if command == "encode" => encode(input, recipe, output, None),
+        [command, input, recipe, output, option, outcome] if command == "encode" && option == "--outcome" => {
+            encode(input, recipe, output, Some(outcome))
+        }
+        [command] if command == "--help" || command == "-h" => {
+            println!("{USAGE}");
+            EXIT_SUCCESS
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            EXIT_USAGE
+        }
+    }
+}
+
+// Without an outcome variable, the one of the project is used, or else the last one of the input file.
+fn encode (input: &str, recipe: &str, output: &str, outcome: Option<&str>) -> i32 {
+    if std::path::Path::new(recipe).extension().is_some_and(|e| e == project::EXTENSION) {
+        return match Project::load(std::path::Path::new(recipe)) {
+            Err(error) => {
+                eprintln!("Project '{recipe}': {error}");
+                EXIT_RECIPE
+            }
+            Ok(project) if outcome.is_some_and(|o| o != project.outcome) => {
+                eprintln!("Project '{recipe}' has outcome variable \u{201c}{}\u{201d}, which can not be changed.", project.outcome);
+                EXIT_USAGE
+            }
+            Ok(project) => encode_with(input, &project.outcome, Some(&project), project.dialect, project.cards.clone(), output)
+        }
+    }
+    let cards: Vec<Card> = match std::fs::read_to_string(recipe) {
+        Err(error) => {
+            eprintln!("Recipe '{recipe}' cannot be read: {error}");
+            return EXIT_RECIPE
+        }
+        Ok(text) => match ron::from_str(&text) {
+            Err(error) => {
+                eprintln!("Recipe '{recipe}' is not valid: {error}");
+                return EXIT_RECIPE
+            }
+            Ok(cards) => cards
+        }
+    };
+    encode_with(input, outcome.unwrap_or_default(), None, Decoder::sniff(input), cards, output)
+}
+
+// Outcome is the name of the outcome variable, or empty for the last one.
+fn encode_with (input: &str, outcome: &str, project: Option<&Project>, dialect: Dialect, mut cards: Vec<Card>, output: &str) -> i32 {
+    let mut variables = Vec::<Variable>::new();
+    let mut rows = 0;
+    let mut report = LoadReport::default();
+    if let Err(error) = Decoder::load(input, &dialect, outcome, &mut variables, &mut rows, &mut report) {
+        eprintln!("Input '{input}' cannot be decoded: {error}");
+        return EXIT_INPUT
+    }
+    if project.is_none() && !outcome.is_empty() && !report.header.iter().any(|n| n == outcome) {
+        eprintln!("Outcome variable \u{201c}{outcome}\u{201d} is not in input '{input}'.");
+        return EXIT_USAGE
+    }
+    if let Some(project) = project && let Err(error) = project.validate(&report.header) {
+        eprintln!("Project does not match input '{input}': {error}");
+        return EXIT_RECIPE
+    }
+    for row in &report.skipped {
+        eprintln!("Skipped row at line {}: {}", row.line, row.reason);
+    }
+    // Outcome variable (interpretable as an f32) is placed last by the decoder.
+    let Some(mut outcome) = variables.pop() else {
+        eprintln!("Input '{input}' has no variables.");
+        return EXIT_INPUT
+    };
+    outcome.as_numbers();
+    if cards.len() > variables.len() {
+        eprintln!("Recipe has {} cards, but input has only {} variables (besides outcome).", cards.len(), variables.len());
+        return EXIT_RECIPE
+    }
+    let targets = match match_cards(&cards, &variables) {
+        Err(error) => {
+            eprintln!("Recipe does not match input '{input}': {error}");
+            return EXIT_RECIPE
+        }
+        Ok(targets) => targets
+    };
+    let mut valid = true;
+    for (card, target) in cards.iter_mut().zip(targets) {
+        let variable = &mut variables[target];
+        let name = variable.name().to_string();
+        card.apply(variable, &outcome);
+        if !card.message.is_empty() {
+            eprintln!("Variable '{name}': {}", card.message);
+            valid = false;
+        }
+        // Warnings are of no concern if the variable is left out (as in the user interface).
+        if !card.warning.is_empty() && card.is_included {
+            eprintln!("Variable '{name}' (warning): {}", card.warning);
+        }
+        let failures: Vec<String> = variable.failures().iter()
+            .filter(|(s, _)| !card.conversions.contains_key(*s))
+            .map(|(s, c)| format!("\u{201c}{s}\u{201d} ({c})"))
+            .collect();
+        if !failures.is_empty() {
+            eprintln!("Variable '{name}' (warning): Not numbers, so missing: {}.", failures.join(", "));
+        }
+        if let Some(problem) = variable.problem() {
+            eprintln!("Variable '{name}': {problem}");
+            valid = false;
+        }
+    }
+    if !valid {
+        return EXIT_RECIPE
+    }
+    if let Err(error) = Encoder::save(std::path::Path::new(output), &variables, &outcome, rows) {
+        eprintln!("Output '{output}' cannot be written: {error}");
+        return EXIT_OUTPUT
+    }
+    let dropped = (0..rows).filter(|i| variables.iter().any(|v| v.drops(*i))).count();
+    eprintln!("Encoded {} rows ({} skipped, {dropped} left out) into {} bit variables.", rows - dropped, report.skipped.len(), variables.iter().map(|v| v.bits().len()).sum::<usize>());
+    EXIT_SUCCESS
+}
+
+// Position of the variable each card belongs to: the variable of its name, or else of its title, or
+// else the one at the position of the card. Cards can not belong to the same variable.
+fn match_cards (cards: &[Card], variables: &[Variable]) -> Result<Vec<usize>, String> {
+    let mut targets = Vec::<usize>::new();
+    for (position, card) in cards.iter().enumerate() {
+        let named = |name: &str| variables.iter().position(|v| v.name() == name);
+        let target = if card.name.is_empty() {
+            named(&card.title).unwrap_or(position)
+        } else {
+            named(&card.name).ok_or_else(|| format!("Variable \u{201c}{}\u{201d} of the recipe is not in the file.", card.name))?
+        };
+        if targets.contains(&target) {
+            return Err(format!("Variable \u{201c}{}\u{201d} has more than one card in the recipe.", variables[target].name()))
+        }
+        targets.push(target);
+    }
+    Ok(targets)
+}
@@ -0,0 +1,13 @@
+/*
+    Console program for headless mode (see cli), taking the same arguments as the application. It is
+    built without the Windows subsystem of the application, so that its diagnostics can be seen.
+*/
+
+#[allow(dead_code)] // Parts of the models are used by the user interface only.
+mod models;
+mod cli;
+
+fn main() {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(cli::run(&arguments));
+}
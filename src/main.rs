@@ -13,18 +13,42 @@ use eframe::egui::{
 };
 
 mod widgets;
+use widgets::chart::{
+    Chart,
+    Edit
+};
 use widgets::errorfield::ErrorField;
 use widgets::switch::Switch;
 
 mod models;
+use models::card::Card;
+
+mod cli;
 use models::variable::Variable;
 use models::variable::Mapping;
-use models::decoder::Decoder;
+use models::variable::Value;
+use models::variable::BitOrder;
+use models::variable::OtherValues;
+use models::variable::MissingValues;
+use models::variable::Imputation;
+use models::variable::RareValues;
+use models::variable::Conversion;
+use models::decoder::{
+    Decoder,
+    LoadReport
+};
+use models::dialect::{
+    Dialect,
+    DELIMITERS,
+    QUOTATIONS
+};
 use models::encoder::Encoder;
-use models::parser::Parser;
+use models::project::Project;
+use models::statistics::Statistics;
 
 const WINDOW_SIZE:  egui::Vec2 = egui::Vec2::new(640.0, 480.0);
 const ACCENT_COLOR: egui::Color32 = egui::Color32::from_rgb(204, 136, 0); // HSL(40,100,40)
+const BIT_BUDGET:   usize = 1000; // Default maximum number of bit variables, before warning.
 
 #[derive(serde::Deserialize, serde::Serialize, PartialEq, Copy, Clone)]
 enum InterfaceMode
@@ -33,6 +57,15 @@ enum InterfaceMode
     Light
 }
 
+#[derive(PartialEq, Clone, Copy)]
+enum Prompt
+{
+    SaveAs,      // Asking for path of output file.
+    OpenProject, // Asking for path of project file to open.
+    SaveProject, // Asking for path of project file to save.
+    OverBudget   // Asking to save output file (at path, if any) with more bit variables than budget.
+}
+
 #[derive(Default, PartialEq)]
 enum StateTracker 
 {
@@ -41,28 +74,6 @@ enum StateTracker
     #[default] Idle
 }
 
-#[derive(Default, serde::Deserialize, serde::Serialize)]
-struct Card 
-{
-    expression: String, // Expression to cluster values (if in Cluster mode).
-    is_included: bool,  // If variable is included (GUI)
-    is_numeric: bool,   // If variable should be perceived as having string or numeric values.
-    title: String,      // Title of variable that can be edited.
-    #[serde(skip)]
-    message: String,    // Message after parsing expression.
-}
-
-impl Card 
-{
-    fn new (name: &str) -> Self {
-        Self {
-            is_included: true,
-            title: name.to_string(),
-            ..Default::default()
-        }
-    }
-}
-
 // Extract only message from an Result error by adding a new trait to Result (go Rust!).
 trait MessageOnly 
 {
@@ -80,10 +91,13 @@ impl <T,E> MessageOnly for Result<T,E> where E: ToString
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 struct Bitcoder
 {
     ui_size: f32,
     ui_mode: InterfaceMode,
+    output: String, // Folder of last saved file.
+    budget: usize,  // Maximum number of bit variables before warning, none if zero.
 
     // #[serde(skip)] storage: dyn eframe::Storage,
     #[serde(skip)] variables: Vec<Variable>,
@@ -92,6 +106,11 @@ struct Bitcoder
     #[serde(skip)] cards: Vec<Card>,
     #[serde(skip)] error: String,
     #[serde(skip)] path: String,
+    #[serde(skip)] dialect: Dialect,
+    #[serde(skip)] target: String, // Name of outcome variable as chosen, last one if empty.
+    #[serde(skip)] report: LoadReport,
+    #[serde(skip)] show_report: bool,
+    #[serde(skip)] prompt: Option<(Prompt, String)>,
     #[serde(skip)] state: StateTracker
 }
 
@@ -101,12 +120,19 @@ impl Default for Bitcoder
         Self {
             ui_size: 1.2,
             ui_mode: InterfaceMode::Dark,
+            output: String::new(),
+            budget: BIT_BUDGET,
             variables: Vec::new(),
             rows: 0,
             outcome: Variable::default(),
             cards: Vec::new(),
             error: String::new(),
             path: String::new(),
+            dialect: Dialect::default(),
+            target: String::new(),
+            report: LoadReport::default(),
+            show_report: false,
+            prompt: None,
             state: StateTracker::Idle
         }
     }
@@ -193,8 +219,8 @@ impl Bitcoder
         }
     }
 
-    fn get_card_frame (&mut self, index: usize) -> egui::Frame {
-        let color = if self.cards[index].is_included {ACCENT_COLOR} else {Color32::GRAY};
+    fn get_card_frame (&mut self, index: usize, is_over: bool) -> egui::Frame {
+        let color = if is_over {Color32::RED} else if self.cards[index].is_included {ACCENT_COLOR} else {Color32::GRAY};
         self.get_main_frame()
             .inner_margin(18.0)
             .outer_margin(4.0)
@@ -212,8 +238,9 @@ impl Bitcoder
             .stroke(egui::Stroke::new(2.0, ACCENT_COLOR.gamma_multiply(0.2)))
     }
 
-    fn ui_card (&mut self, ui: &mut egui::Ui, index: usize) {
-        self.get_card_frame(index).show(ui, |ui| {
+    // Share is the number of bits this variable may have, if it contributes most to exceeding the bit budget.
+    fn ui_card (&mut self, ui: &mut egui::Ui, index: usize, share: Option<usize>) {
+        self.get_card_frame(index, share.is_some()).show(ui, |ui| {
             let variable = &mut self.variables[index];
             let card = &mut self.cards[index];
             ui.horizontal(|ui| {
@@ -241,23 +268,13 @@ impl Bitcoder
             }
             ui.horizontal(|ui| {
                 ui.vertical(|ui| {
-                    let card = &mut self.cards[index];
-                    if ui.checkbox(&mut card.is_included, "Include this").changed() && card.is_included {
-                        variable.include();
-                    }
-                    if ui.checkbox(&mut card.is_numeric, "As numeric").changed() {
-                        if card.is_numeric {
-                            variable.as_numbers();
-                        } else {
-                            variable.as_strings();
-                        }
-                    }
+                    Self::ui_card_options(ui, index, &mut self.cards[index], variable);
                 });
                 ui.add_space(24.0);
                 ui.vertical(|ui| {
                     let (is_recoded, is_cluster) = match variable.mapping() {
                         Mapping::Recode => (true, false),
-                        Mapping::Cluster {..} => (false, true)
+                        Mapping::Cluster {..} | Mapping::Set {..} | Mapping::Pattern {..} => (false, true)
                     };
                     if ui.radio(is_recoded, "Recode all unique values").clicked() {
                         variable.set_recoded();
@@ -269,40 +286,245 @@ impl Bitcoder
                         ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
                             let card = &mut self.cards[index];
                             if ui.add(ErrorField::new(&mut card.expression, card.message.is_empty())).changed() {
-                                match Parser::parse(&card.expression) {
-                                    Err(m) => card.message = m,
-                                    Ok (t) => {
-                                        match variable.use_ranges(&t) {
-                                            Err(m)  => card.message = m.to_string(),
-                                            Ok (()) => card.message.clear()
-                                        }
-                                    }
-                                }
+                                card.use_expression(variable, &self.outcome);
                             }
                             if !card.message.is_empty() {
-                                ui.label(egui::RichText::new(&self.cards[index].message).color(egui::Color32::RED));
+                                ui.label(egui::RichText::new(&card.message).color(egui::Color32::RED));
+                            }
+                            if !card.warning.is_empty() {
+                                ui.label(egui::RichText::new(&card.warning).color(ui.visuals().warn_fg_color));
+                            }
+                            if !card.ranges.is_empty() {
+                                ui.horizontal(|ui| {
+                                    if ui.small_button("\u{e897} Freeze").on_hover_text("Use these ranges as expression").clicked() {
+                                        card.freeze(variable, &self.outcome);
+                                    }
+                                    ui.label(egui::RichText::new(&card.ranges).weak());
+                                });
                             }
                         });
                     }
-                    // Add boxplot? https://github.com/emilk/egui_plot and https://github.com/emilk/egui_plot/issues/9
                 });
             });
+            Self::ui_card_chart(ui, &mut self.cards[index], variable, &self.outcome);
+            Self::ui_card_outcome(ui, index, variable, &self.outcome, self.rows);
             ui.separator();
-            ui.label(format!("Results in {} bit variables ({} missing). Ranges from {} to {}", 
-                variable.density().len(), // = number of clusters or number of unique values if recoded.
-                variable.missing(), 
-                variable.minimum(), 
-                variable.maximum())
+            Self::ui_card_summary(ui, variable, share);
+            Self::ui_card_failures(ui, index, &mut self.cards[index], variable);
+        });
+    }
+
+    // Problem, results and statistics of a variable, below its card. Associated function, as ui_card_options.
+    fn ui_card_summary (ui: &mut egui::Ui, variable: &Variable, share: Option<usize>) {
+        if let Some(problem) = variable.problem() {
+            ui.label(egui::RichText::new(problem).color(egui::Color32::RED));
+        }
+        let imputed = if variable.imputed() > 0 { format!(", {} imputed", variable.imputed()) } else { String::new() };
+        ui.label(format!("Results in {} bit variables ({} missing{}). Ranges from {} to {}", 
+            variable.density().len(), // = number of clusters or number of unique values if recoded.
+            variable.missing() + variable.imputed(), // Imputed values were missing.
+            imputed,
+            variable.minimum(), 
+            variable.maximum())
+        );
+        let statistics = variable.statistics();
+        if statistics.count > 0 {
+            ui.label(format!("Mean {:.3}, standard deviation {:.3}, median {} (quartiles {} and {}), skewness {:.2}, {} distinct values.",
+                statistics.mean,
+                statistics.deviation,
+                statistics.median,
+                statistics.first,
+                statistics.third,
+                statistics.skewness,
+                statistics.distinct)
             );
+        }
+        if variable.dropped() > 0 {
+            ui.label(format!("{} rows will be left out.", variable.dropped()));
+        }
+        if let Some(share) = share {
+            ui.label(egui::RichText::new(format!("Contributes most to exceeding the bit budget (more than {share} bits).")).color(ui.visuals().warn_fg_color));
+        }
+    }
+
+    // Strings that could not be read as numbers, with what they are converted to instead. Associated function, as ui_card_options.
+    fn ui_card_failures (ui: &mut egui::Ui, index: usize, card: &mut Card, variable: &mut Variable) {
+        let total: usize = variable.failures().values().sum();
+        if total == 0 {
+            return;
+        }
+        let header = egui::RichText::new(format!("{total} values could not be read as numbers")).color(ui.visuals().warn_fg_color);
+        egui::CollapsingHeader::new(header).id_salt(("Failures", index)).show(ui, |ui| {
+            let mut changed = false;
+            egui::Grid::new(("Failures", index)).show(ui, |ui| {
+                for (string, count) in variable.failures() {
+                    let mut conversion = card.conversions.get(string).copied().unwrap_or_default();
+                    let mut edited = false;
+                    ui.label(format!("\u{201c}{string}\u{201d}"));
+                    ui.label(format!("{count} rows"));
+                    egui::ComboBox::from_id_salt(("Conversion", index, string)).selected_text(conversion.to_string()).show_ui(ui, |ui| {
+                        for option in [Conversion::Missing, Conversion::Number { number: 0.0 }, Conversion::Bit] {
+                            let selected = std::mem::discriminant(&conversion) == std::mem::discriminant(&option);
+                            if ui.selectable_label(selected, option.to_string()).clicked() && !selected {
+                                conversion = option;
+                                edited = true;
+                            }
+                        }
+                    });
+                    if let Conversion::Number { number } = &mut conversion && ui.add(egui::DragValue::new(number)).changed() {
+                        edited = true;
+                    }
+                    if edited {
+                        card.conversions.insert(string.clone(), conversion);
+                        changed = true;
+                    }
+                    ui.end_row();
+                }
+            });
+            if changed {
+                variable.set_conversions(&card.conversions);
+            }
         });
     }
 
+    // Histogram of numeric values with cluster boundaries that can be edited, or frequency of each bit variable.
+    // Edits change the expression, which is then used as if typed. Associated function, as ui_card_options.
+    fn ui_card_chart (ui: &mut egui::Ui, card: &mut Card, variable: &mut Variable, outcome: &Variable) {
+        if let (Value::Number { number: minimum }, Value::Number { number: maximum }) = (variable.minimum(), variable.maximum()) {
+            let mut edit = None;
+            ui.add(Chart::histogram(variable.frequencies(), *minimum, *maximum).lines(variable.boundaries()).editable(&mut edit));
+            let expression = match edit {
+                Some(Edit::Move { from, to }) => variable.move_boundary(from, to),
+                Some(Edit::Add { at }) => variable.add_boundary(at),
+                Some(Edit::Remove { at }) => variable.remove_boundary(at),
+                None => None
+            };
+            if let Some(expression) = expression {
+                card.expression = expression;
+                card.use_expression(variable, outcome);
+            }
+        } else {
+            let density = variable.density();
+            ui.add(Chart::new(variable.bits().iter().map(|b| (b.clone(), density.get(b).copied().unwrap_or(0))).collect()));
+        }
+    }
+
+    // Table of the outcome of the rows of each bit variable, to judge if clusters are informative. A binary
+    // outcome is shown as the rate of its higher value. Only computed when shown.
+    #[allow(clippy::cast_precision_loss)] // Counts are far below the precision of f32.
+    fn ui_card_outcome (ui: &mut egui::Ui, index: usize, variable: &Variable, outcome: &Variable, rows: usize) {
+        egui::CollapsingHeader::new("Outcome per bit variable").id_salt(("Outcome", index)).show(ui, |ui| {
+            // Only numbers of the outcome count, not bits of missing or other values.
+            let summary = outcome.statistics();
+            if summary.count == 0 {
+                ui.label("The outcome variable has no numeric values.");
+                return;
+            }
+            let positive = match outcome.maximum() {
+                Value::Number { number } if summary.distinct == 2 => Some(*number),
+                _ => None
+            };
+            let header: Vec<String> = if let Some(positive) = positive { vec![String::from("RATE OF ") + &positive.to_string()] } else { vec!["MEAN".into(), "MEDIAN".into(), "STD".into()] };
+            egui::Grid::new(("Outcomes", index)).striped(true).show(ui, |ui| {
+                for title in ["BIT", "COUNT", "SHARE"].iter().copied().chain(header.iter().map(String::as_str)) {
+                    ui.label(egui::RichText::new(title).small().weak());
+                }
+                ui.end_row();
+                for (bit, values) in variable.outcomes_by_bit(outcome) {
+                    let statistics = Statistics::of(&values);
+                    ui.label(bit.strip_prefix(&(variable.name().to_string() + "|")).unwrap_or(bit));
+                    ui.label(statistics.count.to_string());
+                    ui.label(format!("{:.1}%", 100.0 * statistics.count as f32 / rows.max(1) as f32));
+                    if let Some(positive) = positive {
+                        let hits = values.iter().filter(|v| v.total_cmp(&positive).is_eq()).count();
+                        ui.label(format!("{:.1}%", 100.0 * hits as f32 / statistics.count.max(1) as f32));
+                    } else {
+                        ui.label(format!("{:.3}", statistics.mean));
+                        ui.label(format!("{:.3}", statistics.median));
+                        ui.label(format!("{:.3}", statistics.deviation));
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    // Associated function instead of method, as variable and card are borrowed from self.
+    fn ui_card_options (ui: &mut egui::Ui, index: usize, card: &mut Card, variable: &mut Variable) {
+        if ui.checkbox(&mut card.is_included, "Include this").changed() && card.is_included {
+            variable.include();
+        }
+        if ui.checkbox(&mut card.is_numeric, "As numeric").changed() {
+            if card.is_numeric {
+                variable.as_numbers();
+            } else {
+                variable.as_strings();
+            }
+        }
+        egui::ComboBox::from_id_salt(("Order", index)).selected_text(card.order.to_string()).show_ui(ui, |ui| {
+            for order in [BitOrder::Natural, BitOrder::Frequency, BitOrder::Alphabetical] {
+                if ui.selectable_value(&mut card.order, order, order.to_string()).changed() {
+                    variable.set_order(order);
+                }
+            }
+        });
+        if !matches!(variable.mapping(), Mapping::Recode) {
+            egui::ComboBox::from_id_salt(("Other", index)).selected_text(card.other.to_string()).show_ui(ui, |ui| {
+                for other in [OtherValues::Bit, OtherValues::Drop, OtherValues::Error, OtherValues::Nearest] {
+                    if ui.selectable_value(&mut card.other, other, other.to_string()).changed() {
+                        variable.set_other_values(other);
+                    }
+                }
+            });
+        }
+        egui::ComboBox::from_id_salt(("Missing", index)).selected_text(card.missing.to_string()).show_ui(ui, |ui| {
+            for missing in [MissingValues::Zeros, MissingValues::Bit, MissingValues::Drop, MissingValues::Impute] {
+                if ui.selectable_value(&mut card.missing, missing, missing.to_string()).changed() {
+                    variable.set_missing_values(missing);
+                }
+            }
+        });
+        if card.missing == MissingValues::Impute {
+            egui::ComboBox::from_id_salt(("Imputation", index)).selected_text(card.imputation.to_string()).show_ui(ui, |ui| {
+                for imputation in [Imputation::Cluster, Imputation::Mean, Imputation::Median, Imputation::Mode, Imputation::Constant] {
+                    if ui.selectable_value(&mut card.imputation, imputation, imputation.to_string()).changed() {
+                        variable.set_imputation(imputation, &card.constant);
+                    }
+                }
+            });
+            if card.imputation == Imputation::Constant && ui.text_edit_singleline(&mut card.constant).changed() {
+                variable.set_imputation(card.imputation, &card.constant);
+            }
+        }
+        if matches!(variable.mapping(), Mapping::Recode) {
+            egui::ComboBox::from_id_salt(("Rare", index)).selected_text(card.rare.to_string()).show_ui(ui, |ui| {
+                for rare in [RareValues::Keep, RareValues::Count, RareValues::Percent] {
+                    if ui.selectable_value(&mut card.rare, rare, rare.to_string()).changed() {
+                        variable.set_rare_values(rare, card.threshold);
+                    }
+                }
+            });
+            if card.rare != RareValues::Keep {
+                let threshold = match card.rare {
+                    RareValues::Percent => egui::DragValue::new(&mut card.threshold).range(0.0..=100.0).speed(0.1).prefix("below ").suffix("%"),
+                    _ => egui::DragValue::new(&mut card.threshold).range(0.0..=f32::MAX).speed(1.0).prefix("below ").fixed_decimals(0)
+                };
+                if ui.add(threshold).changed() {
+                    variable.set_rare_values(card.rare, card.threshold);
+                }
+                // Preview, as the number of bit variables is also shown in the summary line.
+                ui.label(format!("{} values merged, {} bits remain", variable.merged(), variable.density().len()));
+            }
+        }
+    }
+
     fn ui_list (&mut self, ui: &mut egui::Ui) {
         let count = self.variables.len();
         if  count > 0 {
+            let (share, offenders) = self.offenders();
             egui::ScrollArea::vertical().show(ui, |ui| {
                 for index in 0..count {
-                    self.ui_card(ui, index);
+                    self.ui_card(ui, index, offenders.contains(&index).then_some(share));
                 };
             });
         }
@@ -311,15 +533,62 @@ impl Bitcoder
     fn ui_outcome (&mut self, ui: &mut egui::Ui) {
         ui.vertical(|ui| {
             ui.label(egui::RichText::new("OUTCOME VARIABLE:").small().weak());
-            ui.label(egui::RichText::new(self.outcome.name()).heading().color(ACCENT_COLOR));
+            let mut target = self.outcome.name().to_string();
+            egui::ComboBox::from_id_salt("Outcome").selected_text(egui::RichText::new(&target).heading().color(ACCENT_COLOR)).show_ui(ui, |ui| {
+                for name in &self.report.header {
+                    ui.selectable_value(&mut target, name.clone(), name);
+                }
+            });
+            // Reload file when user picks another outcome variable, as rows missing an outcome value are skipped.
+            if target != self.outcome.name() {
+                self.target = target;
+                self.reload_file();
+            }
             ui.label(format!("{} bit variables will be built from {} observations. Outcome ranges from {} to {}", 
-                self.variables.iter().map(|v| v.density().len()).reduce(|a, v| a + v).unwrap_or(0),
+                self.bits(),
                 self.rows,
                 self.outcome.minimum(), 
                 self.outcome.maximum())
             );
+            if self.bits() > self.budget && self.budget > 0 {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(format!("That is more than the bit budget of {}, mostly due to the highlighted variables.", self.budget))
+                        .color(ui.visuals().warn_fg_color));
+                    if ui.small_button("\u{e5d6} Reduce").on_hover_text("Merge rare values of highlighted string variables into Other, and use quantiles for highlighted numeric variables").clicked() {
+                        self.reduce_bits();
+                    }
+                });
+            }
         });
         ui.add_space(12.0);
+        let dialect = self.dialect;
+        ui.horizontal(|ui| {
+            ui.vertical(|ui| {
+                ui.label(egui::RichText::new("DELIMITER").small().weak());
+                egui::ComboBox::from_id_salt("Delimiter").selected_text(self.dialect.delimiter_name()).show_ui(ui, |ui| {
+                    for (delimiter, name) in DELIMITERS {
+                        ui.selectable_value(&mut self.dialect.delimiter, delimiter, name);
+                    }
+                });
+            });
+            ui.vertical(|ui| {
+                ui.label(egui::RichText::new("QUOTATION").small().weak());
+                egui::ComboBox::from_id_salt("Quotation").selected_text(self.dialect.quotation_name()).show_ui(ui, |ui| {
+                    for (quotation, name) in QUOTATIONS {
+                        ui.selectable_value(&mut self.dialect.quotation, quotation, name);
+                    }
+                });
+            });
+            ui.vertical(|ui| {
+                ui.label(egui::RichText::new("HEADER").small().weak());
+                ui.checkbox(&mut self.dialect.has_header, "First row has names");
+            });
+        });
+        // Reload file when user overrides the guessed dialect.
+        if self.dialect != dialect {
+            self.reload_file();
+        }
+        ui.add_space(12.0);
         ui.horizontal(|ui| {
             let dragger = ui.button("\u{e945} Drag to export").interact(egui::Sense::click_and_drag()).highlight();
             if  dragger.drag_started() {
@@ -331,13 +600,53 @@ impl Bitcoder
                 self.state = StateTracker::Idle;
                 if outside {
                     // thread::spawn(|| { // Should be this easy (nogo Rust).
-                        self.save_file();
+                        self.export(None);
                     // });
                 }
             }
             if self.state == StateTracker::Dragging {
                 ui.ctx().set_cursor_icon(if outside { egui::CursorIcon::Grabbing } else { egui::CursorIcon::NoDrop });
             }
+            if ui.button("\u{e161} Save as\u{2026}").clicked() {
+                let path = Encoder::default_path(self.path.as_str(), Some(std::path::Path::new(&self.output)))
+                    .unwrap_or_else(|_| std::path::PathBuf::from(&self.path).with_extension("bitcoder"));
+                self.prompt = Some((Prompt::SaveAs, path.display().to_string()));
+            }
+        });
+    }
+
+    fn ui_report (&mut self, ui: &mut egui::Ui) {
+        ui.label(egui::RichText::new("LOAD REPORT").small().weak());
+        ui.label(format!("{} rows read, {} kept and {} skipped. {} of the kept rows miss one or more predictor values.",
+            self.report.read,
+            self.rows,
+            self.report.skipped.len(),
+            self.report.incomplete)
+        );
+        if !self.report.skipped.is_empty() {
+            egui::CollapsingHeader::new(format!("Skipped rows ({})", self.report.skipped.len())).id_salt("Skipped").show(ui, |ui| {
+                let height = ui.text_style_height(&egui::TextStyle::Body);
+                egui::ScrollArea::vertical().id_salt("Skipped").max_height(120.0).show_rows(ui, height, self.report.skipped.len(), |ui, range| {
+                    for row in &self.report.skipped[range] {
+                        ui.label(format!("Line {}: {}", row.line, row.reason));
+                    }
+                });
+            });
+        }
+        if self.report.missing.iter().any(|m| m.1 > 0) {
+            egui::CollapsingHeader::new("Missing predictor values").id_salt("Missing").show(ui, |ui| {
+                for (name, count) in self.report.missing.iter().filter(|m| m.1 > 0) {
+                    ui.label(format!("{name}: {count} rows"));
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            if !self.report.skipped.is_empty() && ui.button("\u{e2c4} Export skipped rows").clicked() {
+                self.save_skipped();
+            }
+            if ui.button("\u{e5cd} Dismiss").clicked() {
+                self.show_report = false;
+            }
         });
     }
 
@@ -365,48 +674,215 @@ impl Bitcoder
                     }
                 }
             });
+            ui.add_space(24.0);
+            ui.vertical(|ui| {
+                ui.label(egui::RichText::new("BIT BUDGET").small().weak());
+                ui.add(egui::DragValue::new(&mut self.budget).range(0..=usize::MAX).speed(10.0))
+                    .on_hover_text("Warn before saving more bit variables than this (none if 0)");
+            });
+            ui.add_space(24.0);
+            ui.vertical(|ui| {
+                ui.label(egui::RichText::new("PROJECT").small().weak());
+                ui.horizontal(|ui| {
+                    if ui.button("Open\u{2026}").clicked() {
+                        self.prompt = Some((Prompt::OpenProject, self.output.clone()));
+                    }
+                    if ui.add_enabled(!self.path.is_empty(), egui::Button::new("Save\u{2026}")).clicked() {
+                        let path = std::path::PathBuf::from(&self.path).with_extension(models::project::EXTENSION);
+                        self.prompt = Some((Prompt::SaveProject, path.display().to_string()));
+                    }
+                });
+            });
         });
     }
 
-    fn load_file (&mut self, storage: Option<&dyn eframe::Storage>) {
-        self.error = Decoder::load(self.path.as_str(), &mut self.variables, &mut self.rows).as_message();
-        self.cards = Vec::with_capacity(self.variables.len());
-        // Last variable is the outcome variable (interpretable as an f32).
-        if let Some(variable) = self.variables.pop() {
-            self.outcome = variable;
-            self.outcome.as_numbers();
+    fn ui_prompt (&mut self, ui: &mut egui::Ui) {
+        let Some((prompt, _)) = self.prompt else {
+            return;
+        };
+        let (title, button) = match prompt {
+            Prompt::SaveAs => ("Save bit strings as:", "Save"),
+            Prompt::OpenProject => ("Open project file:", "Open"),
+            Prompt::SaveProject => ("Save project as:", "Save"),
+            Prompt::OverBudget => ("Bit budget exceeded:", "Save anyway")
+        };
+        let detail = format!("{} bit variables will be built, which is more than the bit budget of {}.", self.bits(), self.budget);
+        let mut action = None; // Accept (true) or cancel (false).
+        egui::Modal::new(egui::Id::new("Prompt")).frame(self.get_over_frame()).show(ui.ctx(), |ui| {
+            ui.set_width(320.0);
+            ui.style_mut().spacing.item_spacing = egui::Vec2::new(18.0, 12.0);
+            ui.label(egui::RichText::new(title).color(ACCENT_COLOR).weak());
+            if prompt == Prompt::OverBudget {
+                ui.label(egui::RichText::new(&detail).strong());
+            } else if let Some((_, path)) = &mut self.prompt {
+                ui.add(egui::TextEdit::singleline(path).desired_width(f32::INFINITY));
+            }
+            ui.horizontal(|ui| {
+                if ui.button(button).clicked() {
+                    action = Some(true);
+                }
+                if ui.button("Cancel").clicked() {
+                    action = Some(false);
+                }
+            });
+        });
+        match action {
+            Some(true) => if let Some((prompt, path)) = self.prompt.take() {
+                let path = (!path.is_empty()).then(|| std::path::PathBuf::from(path));
+                match (prompt, path) {
+                    (Prompt::SaveAs, path) => self.export(path),
+                    (Prompt::OverBudget, path) => self.save_file(path),
+                    (Prompt::OpenProject, Some(path)) => self.open_project(&path),
+                    (Prompt::SaveProject, Some(path)) => self.save_project(&path),
+                    (Prompt::OpenProject | Prompt::SaveProject, None) => {}
+                }
+            },
+            Some(false) => self.prompt = None,
+            None => {}
         }
-        if let Some(name) = std::path::PathBuf::from(&self.path).file_name() && let Some(name) = name.to_str() && let Some(storage) = storage {
-            self.cards = eframe::get_value(storage, name).unwrap_or_default();
-            self.cards.iter_mut().enumerate().for_each(|c| {
-                if c.1.title != self.variables[c.0].name() {
-                    self.variables[c.0].set_name(&c.1.title);
+    }
+
+    // Settings of a file, as last used, are stored by its full path.
+    fn open_file (&mut self, storage: Option<&dyn eframe::Storage>) {
+        // Use dialect as last chosen for this file, or guess it.
+        self.dialect = storage
+            .and_then(|storage| eframe::get_value(storage, &(self.path.clone() + "|dialect")))
+            .unwrap_or_else(|| Decoder::sniff(self.path.as_str()));
+        self.target = storage
+            .and_then(|storage| eframe::get_value(storage, &(self.path.clone() + "|outcome")))
+            .unwrap_or_default();
+        self.load_file();
+        let cards = storage.and_then(|storage| eframe::get_value(storage, &self.path)).unwrap_or_default();
+        self.apply_cards(cards);
+    }
+
+    fn open_project (&mut self, path: &std::path::Path) {
+        match Project::load(path) {
+            Err(m) => self.error = m,
+            Ok (project) => {
+                self.path.clone_from(&project.input);
+                self.dialect = project.dialect;
+                self.target.clone_from(&project.outcome);
+                self.load_file();
+                if !self.error.is_empty() {
+                    return;
                 }
-                if c.1.is_numeric {
-                    self.variables[c.0].as_numbers();
+                match project.validate(&self.report.header) {
+                    Err(m)  => self.error = m,
+                    Ok (()) => self.apply_cards(project.cards)
                 }
-                if !c.1.expression.is_empty() {
-                    match Parser::parse(&c.1.expression) {
-                        Err(m) => c.1.message = m,
-                        Ok (t) => {
-                            match self.variables[c.0].use_ranges(&t) {
-                                Err(m)  => c.1.message = m.to_string(),
-                                Ok (()) => c.1.message.clear()
-                            }
-                        }
-                    }
+                if let Some(folder) = path.parent() {
+                    self.output = folder.display().to_string();
                 }
-            });
+            }
+        }
+    }
+
+    fn save_project (&mut self, path: &std::path::Path) {
+        let mut project = Project::new(&self.path, self.dialect, self.outcome.name(), std::mem::take(&mut self.cards));
+        self.error = project.save(path).as_message();
+        self.cards = project.cards;
+        if self.error.is_empty() && let Some(folder) = path.parent() {
+            self.output = folder.display().to_string();
+        }
+    }
+
+    // Reads file and creates a card with default settings for each variable.
+    fn load_file (&mut self) {
+        self.error = Decoder::load(self.path.as_str(), &self.dialect, &self.target, &mut self.variables, &mut self.rows, &mut self.report).as_message();
+        self.show_report = self.error.is_empty();
+        // Outcome variable (interpretable as an f32) is placed last by the decoder.
+        self.outcome = self.variables.pop().unwrap_or_default();
+        self.outcome.as_numbers();
+        self.cards = self.variables.iter().map(|v| Card::new(v.name())).collect();
+    }
+
+    // Reads file again (after changing dialect or outcome), keeping settings of variables.
+    fn reload_file (&mut self) {
+        let cards = std::mem::take(&mut self.cards);
+        self.load_file();
+        self.apply_cards(cards);
+    }
+
+    // Applies settings to the variables of a newly loaded file. Cards are matched by variable name, or by
+    // position if they have no name. Variables without a card keep default settings.
+    fn apply_cards (&mut self, cards: Vec<Card>) {
+        let mut cards: Vec<Option<Card>> = cards.into_iter().map(Some).collect();
+        for (index, variable) in self.variables.iter_mut().enumerate() {
+            let found = cards.iter().position(|c| c.as_ref().is_some_and(|c| c.name == variable.name()))
+                .or_else(|| cards.get(index).and_then(Option::as_ref).filter(|c| c.name.is_empty()).map(|_| index));
+            if let Some(mut card) = found.and_then(|f| cards[f].take()) {
+                card.apply(variable, &self.outcome);
+                self.cards[index] = card;
+            }
         }
-        // Fill rest of cards collection, or all if none was deserialized.
-        for index in self.cards.len()..self.variables.len() {
-            self.cards.push(Card::new(self.variables[index].name()));
+    }
+
+    // Skipped rows are written next to the input file.
+    fn save_skipped (&mut self) {
+        let mut path = std::path::PathBuf::from(&self.path);
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string() + "-skipped.csv";
+        path.set_file_name(name);
+        self.error = self.report.save_skipped(&path).as_message();
+    }
+
+    // Total number of bit variables of included variables.
+    fn bits (&self) -> usize {
+        self.variables.iter().map(|v| v.density().len()).sum()
+    }
+
+    // If the bit budget is exceeded, the variables with more bits than an equal share of the budget,
+    // along with that share. Variables are ordered by number of bits, most first.
+    fn offenders (&self) -> (usize, Vec<usize>) {
+        if self.budget == 0 || self.bits() <= self.budget {
+            return (0, Vec::new())
+        }
+        let included = self.cards.iter().filter(|c| c.is_included).count();
+        let share = (self.budget / included.max(1)).max(2);
+        let mut offenders: Vec<usize> = (0..self.variables.len()).filter(|i| self.variables[*i].density().len() > share).collect();
+        offenders.sort_by_key(|i| std::cmp::Reverse(self.variables[*i].density().len()));
+        (share, offenders)
+    }
+
+    // Reduces the bits of the variables contributing most to exceeding the bit budget, to their share of it.
+    // Numeric variables are clustered by quantiles, and rare values of recoded string variables merged into Other.
+    fn reduce_bits (&mut self) {
+        let (share, offenders) = self.offenders();
+        for index in offenders {
+            let (card, variable) = (&mut self.cards[index], &mut self.variables[index]);
+            if card.is_numeric {
+                card.expression = format!("quantiles {share}");
+                variable.set_cluster();
+                card.use_expression(variable, &self.outcome);
+            } else if matches!(variable.mapping(), Mapping::Recode) {
+                card.rare = RareValues::Count;
+                card.threshold = variable.rare_threshold(share);
+                variable.set_rare_values(card.rare, card.threshold);
+            }
+        }
+    }
+
+    // Asks before saving if the bit budget is exceeded.
+    fn export (&mut self, path: Option<std::path::PathBuf>) {
+        if self.budget > 0 && self.bits() > self.budget {
+            self.prompt = Some((Prompt::OverBudget, path.map(|p| p.display().to_string()).unwrap_or_default()));
+        } else {
+            self.save_file(path);
         }
     }
 
-    fn save_file (&mut self) {
+    // Without a path, file is saved in the folder last saved to (or on the desktop).
+    fn save_file (&mut self, path: Option<std::path::PathBuf>) {
         self.state = StateTracker::Saving;
-        self.error = Encoder::save(self.path.as_str(), &self.variables, &self.outcome, self.rows).as_message();
+        match path.map_or_else(|| Encoder::default_path(self.path.as_str(), Some(std::path::Path::new(&self.output))), Ok) {
+            Err(m) => self.error = m.to_string(),
+            Ok (path) => {
+                self.error = Encoder::save(&path, &self.variables, &self.outcome, self.rows).as_message();
+                if self.error.is_empty() && let Some(folder) = path.parent() {
+                    self.output = folder.display().to_string();
+                }
+            }
+        }
         self.state = StateTracker::Idle;
     }
     
@@ -416,8 +892,10 @@ impl App for Bitcoder
 {
     fn save (&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, eframe::APP_KEY, self);
-        if !self.path.is_empty() && let Some(name) = std::path::PathBuf::from(&self.path).file_name() && let Some(name) = name.to_str() {
-            eframe::set_value(storage, name, &self.cards);
+        if !self.path.is_empty() {
+            eframe::set_value(storage, &self.path, &self.cards);
+            eframe::set_value(storage, &(self.path.clone() + "|dialect"), &self.dialect);
+            eframe::set_value(storage, &(self.path.clone() + "|outcome"), &self.target);
         }
     }
 
@@ -425,23 +903,34 @@ impl App for Bitcoder
         Panel::bottom("Settings").frame(self.get_main_frame()).resizable(false).show(ui, |ui| {
             self.ui_settings(ui);
         });
-        if !self.variables.is_empty() {
+        if !self.path.is_empty() {
             egui::Panel::bottom("Variable").frame(self.get_main_frame()).resizable(false).show(ui, |ui| {
                 self.ui_outcome(ui);
             });
         }
+        if self.show_report && !self.variables.is_empty() {
+            egui::Panel::top("Report").frame(self.get_main_frame()).resizable(false).show(ui, |ui| {
+                self.ui_report(ui);
+            });
+        }
         egui::CentralPanel::default().frame(self.get_main_frame()).show(ui, |ui| {
             if !self.error.is_empty() {
                 egui::Modal::new(egui::Id::new("Dialog")).frame(self.get_over_frame()).show(ui.ctx(), |ui| {
-                    ui.set_width(240.0);
+                    ui.set_width(320.0);
                     ui.style_mut().spacing.item_spacing = egui::Vec2::new(18.0, 12.0);
                     ui.label(egui::RichText::new("Oh no! An error occured.").color(ACCENT_COLOR).weak());
-                    ui.label(egui::RichText::new(&self.error).strong());
+                    // Details, such as the offending row of a file, follow on the next line.
+                    let (message, detail) = self.error.split_once('\n').unwrap_or((&self.error, ""));
+                    ui.label(egui::RichText::new(message).strong());
+                    if !detail.is_empty() {
+                        ui.label(egui::RichText::new(detail).monospace().weak());
+                    }
                     if ui.button("Ok").clicked() {
                         self.error.clear();
                     }
                 });
             }
+            self.ui_prompt(ui);
             let mut hovered = egui::HoveredFile::default();
             let mut dropped = egui::DroppedFile::default();
             ui.ctx().input(|input| {
@@ -458,8 +947,12 @@ impl App for Bitcoder
                 );
             }
             if dropped.path.is_some() && let Some(path) = &dropped.path {
-                self.path = path.display().to_string();
-                self.load_file(frame.storage());
+                if path.extension().is_some_and(|e| e == models::project::EXTENSION) {
+                    self.open_project(path);
+                } else {
+                    self.path = path.display().to_string();
+                    self.open_file(frame.storage());
+                }
             }
             if self.variables.is_empty() {
                 ui.add_sized(ui.available_size(), egui::Label::new(egui::RichText::new("(drop file here)").heading().italics().weak()));
@@ -472,6 +965,12 @@ impl App for Bitcoder
 }
 
 fn main() -> eframe::Result {
+    // Command encode (or help) means headless mode, other arguments (such as a file opened with the
+    // application) start the user interface as usual.
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    if matches!(arguments.first().map(String::as_str), Some("encode" | "--help" | "-h")) {
+        std::process::exit(cli::run(&arguments));
+    }
     eframe::run_native(
         "Bitcoder", 
         eframe::NativeOptions {
@@ -1,4 +1,9 @@
 pub mod parser;
 pub mod variable;
+pub mod binning;
+pub mod statistics;
+pub mod card;
+pub mod project;
+pub mod dialect;
 pub mod decoder;
 pub mod encoder;
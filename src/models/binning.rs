@@ -0,0 +1,171 @@
+/*
+    Supervised binning: finds cut points of a numeric variable that best predict the outcome value.
+
+    Values are split in two at the cut that best separates the outcome, and each part is split again
+    until no cut is worth it, or would leave fewer than the minimum number of values on either side.
+    - If the outcome has few unique values (classes), the cut that minimizes class entropy is chosen,
+      and it is kept only if the information gained passes the MDL criterion (Fayyad & Irani, 1993).
+    - Otherwise the cut that minimizes the sum of squared deviations (variance) is chosen, and it is
+      kept only if it reduces the total sum of squares by a minimum share.
+
+    Cuts are placed halfway between the values on either side, and equal values are never split.
+*/
+
+const MAXIMUM_CLASSES: usize = 10;    // Outcomes with more unique values are treated as continuous.
+const MINIMUM_REDUCTION: f64 = 0.01;  // Share of total sum of squares a cut must remove to be kept.
+
+pub struct Binning;
+impl Binning
+{
+    // Pairs are (value, outcome) and must be sorted by value. Returns the cut points in ascending order.
+    pub fn cuts (pairs: &[(f32, f32)], size: usize) -> Vec<f32> {
+        let mut classes: Vec<f32> = pairs.iter().map(|p| p.1).collect();
+        classes.sort_by(f32::total_cmp);
+        classes.dedup();
+        let mut cuts = Vec::<usize>::new();
+        if classes.len() <= MAXIMUM_CLASSES {
+            let labels: Vec<(f32, usize)> = pairs.iter()
+                .map(|p| (p.0, classes.binary_search_by(|c| c.total_cmp(&p.1)).unwrap_or(0)))
+                .collect();
+            Self::split_classes(&labels, classes.len(), size, 0, &mut cuts);
+        } else {
+            let total = Self::squares(pairs.iter().map(|p| f64::from(p.1)));
+            Self::split_variance(pairs, size, total * MINIMUM_REDUCTION, 0, &mut cuts);
+        }
+        cuts.sort_unstable();
+        cuts.iter().map(|c| f32::midpoint(pairs[c - 1].0, pairs[*c].0)).collect()
+    }
+
+    // If a cut before position leaves at least the minimum number of values on both sides, without splitting equal values.
+    fn is_candidate<T> (pairs: &[(f32, T)], size: usize, position: usize) -> bool {
+        position >= size && pairs.len() - position >= size && pairs[position - 1].0 < pairs[position].0
+    }
+
+    #[allow(clippy::cast_precision_loss)] // Counts are far below the precision of f64.
+    fn split_classes (labels: &[(f32, usize)], classes: usize, size: usize, offset: usize, cuts: &mut Vec<usize>) {
+        let mut whole = vec![0; classes];
+        for label in labels {
+            whole[label.1] += 1;
+        }
+        let all = labels.len() as f64;
+        let mut left = vec![0; classes];
+        let mut best: Option<(usize, f64, Vec<usize>)> = None; // Position, weighted entropy and class counts left of best cut.
+        for position in 1..labels.len() {
+            left[labels[position - 1].1] += 1;
+            if !Self::is_candidate(labels, size, position) {
+                continue;
+            }
+            let right: Vec<usize> = whole.iter().zip(&left).map(|(w, l)| w - l).collect();
+            let weighted = (position as f64 * Self::entropy(&left) + (all - position as f64) * Self::entropy(&right)) / all;
+            if best.as_ref().is_none_or(|b| weighted < b.1) {
+                best = Some((position, weighted, left.clone()));
+            }
+        }
+        let Some((position, weighted, left)) = best else {
+            return
+        };
+        // Minimum description length criterion.
+        let right: Vec<usize> = whole.iter().zip(&left).map(|(w, l)| w - l).collect();
+        let present = |counts: &[usize]| counts.iter().filter(|c| **c > 0).count() as f64;
+        let (k, k1, k2) = (present(&whole), present(&left), present(&right));
+        let entropy = Self::entropy(&whole);
+        let delta = (3f64.powf(k) - 2.0).log2() - (k * entropy - k1 * Self::entropy(&left) - k2 * Self::entropy(&right));
+        if entropy - weighted <= ((all - 1.0).log2() + delta) / all {
+            return
+        }
+        cuts.push(offset + position);
+        Self::split_classes(&labels[..position], classes, size, offset, cuts);
+        Self::split_classes(&labels[position..], classes, size, offset + position, cuts);
+    }
+
+    fn split_variance (pairs: &[(f32, f32)], size: usize, threshold: f64, offset: usize, cuts: &mut Vec<usize>) {
+        let whole = Self::squares(pairs.iter().map(|p| f64::from(p.1)));
+        let (mut count, mut sum, mut square) = (0.0, 0.0, 0.0); // Running totals left of cut.
+        let (total_count, total_sum) = pairs.iter().fold((0.0, 0.0), |(n, s), p| (n + 1.0, s + f64::from(p.1)));
+        let total_square: f64 = pairs.iter().map(|p| f64::from(p.1) * f64::from(p.1)).sum();
+        let mut best: Option<(usize, f64)> = None; // Position and sum of squares of best cut.
+        for position in 1..pairs.len() {
+            let value = f64::from(pairs[position - 1].1);
+            count += 1.0;
+            sum += value;
+            square += value * value;
+            if !Self::is_candidate(pairs, size, position) {
+                continue;
+            }
+            let left = square - sum * sum / count;
+            let right = (total_square - square) - (total_sum - sum) * (total_sum - sum) / (total_count - count);
+            if best.is_none_or(|b| left + right < b.1) {
+                best = Some((position, left + right));
+            }
+        }
+        let Some((position, squares)) = best else {
+            return
+        };
+        if threshold <= 0.0 || whole - squares < threshold {
+            return
+        }
+        cuts.push(offset + position);
+        Self::split_variance(&pairs[..position], size, threshold, offset, cuts);
+        Self::split_variance(&pairs[position..], size, threshold, offset + position, cuts);
+    }
+
+    #[allow(clippy::cast_precision_loss)] // Counts are far below the precision of f64.
+    fn entropy (counts: &[usize]) -> f64 {
+        let all = counts.iter().sum::<usize>() as f64;
+        counts.iter().filter(|c| **c > 0).map(|c| {
+            let share = *c as f64 / all;
+            -share * share.log2()
+        }).sum()
+    }
+
+    // Sum of squared deviations from the mean.
+    fn squares (values: impl Iterator<Item = f64> + Clone) -> f64 {
+        let (count, sum) = values.clone().fold((0.0, 0.0), |(n, s), v| (n + 1.0, s + v));
+        if count < 1.0 {
+            return 0.0
+        }
+        let mean = sum / count;
+        values.map(|v| (v - mean) * (v - mean)).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    // Pairs of the values from first to last, with outcome given by value.
+    fn pairs (first: u8, last: u8, outcome: impl Fn(f32) -> f32) -> Vec<(f32, f32)> {
+        (first..=last).map(|v| (f32::from(v), outcome(f32::from(v)))).collect()
+    }
+
+    #[test]
+    fn cuts_clean_class_split_at_midpoint () {
+        let pairs = pairs(1, 20, |v| if v <= 10.0 { 0.0 } else { 1.0 });
+        assert_eq!(Binning::cuts(&pairs, 5), vec![10.5]);
+    }
+
+    #[test]
+    fn does_not_cut_below_minimum_size () {
+        let pairs = pairs(1, 20, |v| if v <= 10.0 { 0.0 } else { 1.0 });
+        assert!(Binning::cuts(&pairs, 11).is_empty());
+    }
+
+    #[test]
+    fn never_splits_equal_values () {
+        // Ten equal values with mixed outcomes between two clean classes.
+        let mut pairs = pairs(1, 10, |_| 0.0);
+        pairs.extend((0..10).map(|i| (11.0, if i < 5 { 0.0 } else { 1.0 })));
+        pairs.extend(self::pairs(12, 21, |_| 1.0));
+        let cuts = Binning::cuts(&pairs, 5);
+        assert!(!cuts.is_empty());
+        assert!(cuts.iter().all(|c| pairs.iter().all(|p| p.0.total_cmp(c).is_ne())));
+    }
+
+    #[test]
+    fn splits_continuous_outcome_by_variance () {
+        // More unique outcomes than classes, with a jump after value 20.
+        let pairs = pairs(1, 40, |v| if v <= 20.0 { v } else { v + 100.0 });
+        assert_eq!(Binning::cuts(&pairs, 5), vec![20.5]);
+    }
+}
@@ -0,0 +1,144 @@
+/*
+    Settings of a variable, as edited on its card in the user interface. A list of cards, in the same
+    order as the variables of a file, is also used as a recipe when encoding from the command line.
+*/
+
+use crate::models::parser::{
+    Parser,
+    Token
+};
+use crate::models::variable::{
+    BitOrder,
+    Conversion,
+    Imputation,
+    MissingValues,
+    OtherValues,
+    RareValues,
+    Variable
+};
+use std::collections::BTreeMap;
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Card
+{
+    pub name: String,           // Name of variable, as in header of input file.
+    pub expression: String,     // Expression to cluster values (if in Cluster mode).
+    pub is_included: bool,      // If variable is included (GUI)
+    pub is_numeric: bool,       // If variable should be perceived as having string or numeric values.
+    pub title: String,          // Title of variable that can be edited.
+    pub order: BitOrder,        // Order of bit variables in output.
+    pub other: OtherValues,     // What to do with values outside all clusters.
+    pub missing: MissingValues, // What to do with missing values.
+    pub imputation: Imputation, // How missing values are replaced, if imputed.
+    pub constant: String,       // Value that replaces missing values, if imputed by constant.
+    pub rare: RareValues,       // What to do with rare values, if recoded.
+    pub threshold: f32,         // Number or percentage of observations below which a value is rare.
+    pub conversions: BTreeMap<String, Conversion>, // What strings that are not numbers are converted to (if numeric).
+    #[serde(skip)]
+    pub message: String,        // Message after parsing expression.
+    #[serde(skip)]
+    pub ranges: String,         // Resulting ranges, if expression is automatic.
+    #[serde(skip)]
+    pub warning: String,        // Warning after using expression, such as patterns that no value belongs to.
+}
+
+impl Default for Card
+{
+    fn default () -> Self {
+        Self {
+            name: String::new(),
+            expression: String::new(),
+            is_included: true,
+            is_numeric: false,
+            title: String::new(),
+            order: BitOrder::default(),
+            other: OtherValues::default(),
+            missing: MissingValues::default(),
+            imputation: Imputation::default(),
+            constant: String::new(),
+            rare: RareValues::default(),
+            threshold: 0.0,
+            conversions: BTreeMap::new(),
+            message: String::new(),
+            ranges: String::new(),
+            warning: String::new()
+        }
+    }
+}
+
+impl Card
+{
+    pub fn new (name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            title: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    // Applies all settings to a newly loaded variable.
+    pub fn apply (&mut self, variable: &mut Variable, outcome: &Variable) {
+        if self.title.is_empty() {
+            self.title = variable.name().to_string();
+        } else if self.title != variable.name() {
+            variable.set_name(&self.title);
+        }
+        if !self.conversions.is_empty() {
+            variable.set_conversions(&self.conversions);
+        }
+        if self.is_numeric {
+            variable.as_numbers();
+        }
+        if self.order != BitOrder::default() {
+            variable.set_order(self.order);
+        }
+        if self.other != OtherValues::default() {
+            variable.set_other_values(self.other);
+        }
+        if self.missing != MissingValues::default() {
+            variable.set_missing_values(self.missing);
+        }
+        if self.imputation != Imputation::default() || !self.constant.is_empty() {
+            variable.set_imputation(self.imputation, &self.constant);
+        }
+        if self.rare != RareValues::default() {
+            variable.set_rare_values(self.rare, self.threshold);
+        }
+        if !self.expression.is_empty() {
+            self.use_expression(variable, outcome);
+        }
+        if !self.is_included {
+            variable.exclude();
+        }
+    }
+
+    // Parses expression and clusters values accordingly, message is empty if successful.
+    pub fn use_expression (&mut self, variable: &mut Variable, outcome: &Variable) {
+        self.ranges.clear();
+        self.warning.clear();
+        match Parser::parse(&self.expression) {
+            Err(m) => self.message = m,
+            Ok (t) => {
+                match variable.use_ranges(&t, outcome) {
+                    Err(m)  => self.message = m.to_string(),
+                    Ok (()) => self.message.clear()
+                }
+                if self.message.is_empty() && t.iter().any(Token::is_automatic) {
+                    self.ranges = variable.expression();
+                }
+                let unmatched = variable.unmatched();
+                if self.message.is_empty() && !unmatched.is_empty() {
+                    let patterns: Vec<String> = unmatched.iter().map(|p| format!("\u{201c}{p}\u{201d}")).collect();
+                    self.warning = format!("No value belongs to pattern {}.", patterns.join(", "));
+                }
+            }
+        }
+    }
+
+    // Replaces an automatic expression with the ranges it resulted in.
+    pub fn freeze (&mut self, variable: &mut Variable, outcome: &Variable) {
+        self.expression = std::mem::take(&mut self.ranges);
+        self.use_expression(variable, outcome);
+    }
+}
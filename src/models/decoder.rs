@@ -1,75 +1,391 @@
-/* 
+/*
     Reads and parses a CSV file and creates a vector of variables.
+
+    Records are read by a state machine according to RFC 4180, with delimiter and quotation mark
+    given by a dialect (comma and double quote by default):
+    - Fields are separated by delimiters and records by line breaks (LF or CRLF).
+    - Fields may be enclosed in quotation marks, and then contain delimiters and line breaks.
+    - A quotation mark within a quoted field is escaped by another quotation mark ("").
+    - Whitespace between a closing quote and the next delimiter is discarded, but any other character
+      after a closing quote is kept (along with whitespace before it) rather than lost.
+    - A UTF-8 byte order mark before the header is discarded.
 */
 
 
+use crate::models::dialect::{
+    Dialect,
+    SAMPLE_ROWS
+};
 use crate::models::variable::Variable;
 use std::fs::File;
 use std::io::{
-    BufRead, 
-    BufReader
+    BufRead,
+    BufReader,
+    Write
+};
+use std::{
+    iter::Peekable,
+    str::Chars
 };
+use std::fmt::{
+    Display,
+    Formatter
+};
+
+const SNIPPET_LENGTH: usize = 60; // Number of characters of an offending row shown in errors.
+
+#[derive(Debug)]
+pub enum DecodeError
+{
+    Io { error: std::io::Error },                                            // File could not be opened or read.
+    Empty,                                                                   // File has no rows.
+    InvalidEncoding { line: usize, column: usize },                          // File is not UTF-8, position is 1-based.
+    RaggedRow { line: usize, expected: usize, actual: usize, snippet: String } // Number of values differ from header, line is 1-based.
+}
+
+impl Display for DecodeError
+{
+    fn fmt (&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Io { error } => {
+                write!(formatter, "File cannot be opened or read ({error}). Is it opened somewhere else?")
+            }
+            DecodeError::Empty => {
+                write!(formatter, "The file appears to be empty.")
+            }
+            DecodeError::InvalidEncoding { line, column } => {
+                write!(formatter, "The file is not encoded as UTF-8 (invalid character at line {line}, column {column}).")
+            }
+            DecodeError::RaggedRow { line, expected, actual, snippet } => {
+                write!(formatter, "Row at line {line} has {actual} values, but {expected} were expected.\n{snippet}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum SkipReason
+{
+    MissingOutcome // Value of outcome variable is missing.
+}
+
+impl Display for SkipReason
+{
+    fn fmt (&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::MissingOutcome => write!(formatter, "Missing outcome")
+        }
+    }
+}
+
+pub struct SkippedRow
+{
+    pub line: usize,        // Line number (1-based) where row starts.
+    pub reason: SkipReason, // Why row was left out.
+    pub values: Vec<String> // Values as read from file.
+}
+
+// Summary of a loaded file, accounting for every row that was left out.
+#[derive(Default)]
+pub struct LoadReport
+{
+    pub header: Vec<String>,          // Variable names, as read from file.
+    pub read: usize,                  // Number of rows read, not counting header and empty lines.
+    pub skipped: Vec<SkippedRow>,     // Rows that were left out.
+    pub missing: Vec<(String,usize)>, // Number of kept rows missing a value, per predictor variable.
+    pub incomplete: usize             // Number of kept rows missing at least one predictor value.
+}
+
+impl LoadReport
+{
+    // Writes skipped rows as CSV, with line number and reason before the original values.
+    pub fn save_skipped (&self, path: &std::path::Path) -> Result<(), &'static str> {
+        let quote = |value: &str| format!("\"{}\"", value.replace('"', "\"\""));
+        let mut data = String::from("\"Line\",\"Reason\"");
+        for name in &self.header {
+            data.push(',');
+            data.push_str(&quote(name));
+        }
+        for row in &self.skipped {
+            data.push_str(format!("\n{},{}", row.line, quote(&row.reason.to_string())).as_str());
+            for value in &row.values {
+                data.push(',');
+                data.push_str(&quote(value));
+            }
+        }
+        if let Ok(mut file) = File::create(path) {
+            if file.write_all(data.as_bytes()).is_err() {
+                return Err("Error when writing to file.");
+            }
+        } else {
+            return Err("File could not be opened for writing. Is it open somewhere else?");
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum State
+{
+    FieldStart,    // At the beginning of a field, quoted or not.
+    Unquoted,      // Within a field that is not quoted.
+    Quoted,        // Within a quoted field, delimiters and line breaks are part of the value.
+    QuoteInQuoted, // After a quotation mark within a quoted field, either an escaped quote or the closing one.
+    AfterQuoted    // After the closing quotation mark and whitespace, which is discarded if a delimiter follows.
+}
 
-#[allow(clippy::unwrap_used)]
-static SPLITTER: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
-    // Pre-compiling for performance.
-    regex::Regex::new(r#""[^"]*"|[^,]*"#).unwrap()
-});
-    
 pub struct Decoder;
-impl Decoder 
+impl Decoder
 {
-    pub fn load (path: &str, variables: &mut Vec<Variable>, rows: &mut usize) -> Result<(), & 'static str> {
+    // Guesses the dialect of a file from its first rows.
+    pub fn sniff (path: &str) -> Dialect {
+        if let Ok(file) = File::open(path) {
+            let sample: Vec<String> = BufReader::new(file).lines().take(SAMPLE_ROWS).map_while(Result::ok).collect();
+            return Dialect::sniff(&sample);
+        }
+        Dialect::default()
+    }
+
+    // Outcome variable is given by name (last one if empty or not found), and is moved last in the list of variables.
+    pub fn load (path: &str, dialect: &Dialect, outcome: &str, variables: &mut Vec<Variable>, rows: &mut usize, report: &mut LoadReport) -> Result<(), DecodeError> {
         variables.clear();
         *rows = 0;
-        if let Ok(file) = File::open(path) {
-            let mut lines = BufReader::new(file).lines();
-            if let Some(Ok(row)) = lines.next() { 
-                if let Ok(names) = Self::split(&row) {
-                    for name in names {
-                        variables.push(Variable::new(name));
-                    };
-                }
+        *report = LoadReport::default();
+        let text = match std::fs::read(path).map(String::from_utf8) {
+            Err(error) => return Err(DecodeError::Io { error }),
+            Ok(Err(error)) => {
+                let valid = &error.as_bytes()[..error.utf8_error().valid_up_to()];
+                let mut lines = valid.split(|b| *b == b'\n');
+                return Err(DecodeError::InvalidEncoding {
+                    line: lines.clone().count(),
+                    column: lines.next_back().map_or(0, |l| String::from_utf8_lossy(l).chars().count()) + 1
+                })
+            }
+            Ok(Ok(text)) => text
+        };
+        let mut source = text.strip_prefix('\u{feff}').unwrap_or(&text).chars().peekable();
+        let mut line  = 1;    // Line number (1-based) where next record starts.
+        let mut first = None; // First row of values, if there is no header.
+        if let Some(names) = Self::next(&mut source, dialect, &mut line) {
+            if dialect.has_header {
+                for name in names {
+                    variables.push(Variable::new(&name));
+                };
             } else {
-                return Err("The file appears to be empty.")
+                for index in 1..=names.len() {
+                    variables.push(Variable::new(&format!("Column {index}")));
+                };
+                first = Some((1, names));
             }
-            while let Some(Ok(row)) = lines.next() {
-                if let Ok(values) = Self::split(&row) {
-                    // Skip rows that are missing value of outcome variable (last one).
-                    if values.ends_with(&[""]) {
-                        continue;
-                    }
-                    if values.len() != variables.len() {
-                        return Err("Number of values differ between rows in this file.")
-                    }
-                    for (index, value) in values.into_iter().enumerate() {
-                        variables[index].add_value(value);
-                    };
+        } else {
+            return Err(DecodeError::Empty)
+        }
+        report.header = variables.iter().map(|v| v.name().to_string()).collect();
+        let target = report.header.iter().position(|n| n == outcome).unwrap_or(variables.len().saturating_sub(1));
+        report.missing = report.header.iter().enumerate().filter(|(i, _)| *i != target).map(|(_, n)| (n.clone(), 0)).collect();
+        loop {
+            let (start, values) = if let Some(record) = first.take() { record } else {
+                let start = line;
+                match Self::next(&mut source, dialect, &mut line) {
+                    Some(values) => (start, values),
+                    None => break
                 }
-                *rows += 1;
             };
-        } else {
-            return Err("File cannot be opened. Is it opened somewhere else?")
+            // Skip empty lines.
+            if values.len() == 1 && values[0].is_empty() {
+                continue;
+            }
+            report.read += 1;
+            // Skip rows that are missing value of outcome variable.
+            if values.get(target).is_some_and(String::is_empty) {
+                report.skipped.push(SkippedRow { line: start, reason: SkipReason::MissingOutcome, values });
+                continue;
+            }
+            if values.len() != variables.len() {
+                return Err(DecodeError::RaggedRow {
+                    line: start,
+                    expected: variables.len(),
+                    actual: values.len(),
+                    snippet: Self::snippet(&values, dialect)
+                })
+            }
+            for (index, value) in values.iter().enumerate() {
+                variables[index].add_value(value);
+            };
+            let mut complete = true;
+            let predictors = values.iter().enumerate().filter(|(i, _)| *i != target).map(|(_, v)| v);
+            for (missing, value) in report.missing.iter_mut().zip(predictors) {
+                if value.is_empty() {
+                    missing.1 += 1;
+                    complete = false;
+                }
+            }
+            if !complete {
+                report.incomplete += 1;
+            }
+            *rows += 1;
+        };
+        if target < variables.len() {
+            let variable = variables.remove(target);
+            variables.push(variable);
+        }
+        for variable in variables.iter_mut() {
+            variable.arrange();
         }
         Ok(())
     }
 
-    fn split (row: &str) -> Result<Vec<&str>, &str> {
-        if row.is_empty() {
-            return Err("Nothing to split.");
-        }
-        let mut result = Vec::new();
-        let bytes  = row.as_bytes();
-        let slices = SPLITTER.find_iter(row);
-        for slice in slices {
-            let mut lower = slice.range().start;
-            let mut upper = slice.range().end;
-            if  lower < bytes.len() && bytes[lower] == b'"' && bytes[upper-1] == b'"' {
-                lower += 1;
-                upper -= 1;
+    // Reads the next record, possibly spanning several lines, or returns None at end of input.
+    // Line number is increased by every line break read.
+    fn next (source: &mut Peekable<Chars<'_>>, dialect: &Dialect, line: &mut usize) -> Option<Vec<String>> {
+        source.peek()?;
+        let mut state  = State::FieldStart;
+        let mut field  = String::new();
+        let mut record = Vec::new();
+        let mut spaces = String::new(); // Whitespace after closing quotation mark, kept only if followed by other characters.
+        while let Some(character) = source.next() {
+            match state {
+                State::FieldStart | State::Unquoted => {
+                    match character {
+                        _ if character == dialect.quotation && state == State::FieldStart => state = State::Quoted,
+                        _ if character == dialect.delimiter => {
+                            record.push(std::mem::take(&mut field));
+                            state = State::FieldStart;
+                        }
+                        '\r' | '\n' => {
+                            Self::skip_line_feed(source, character);
+                            break;
+                        }
+                        _ => {
+                            field.push(character);
+                            state = State::Unquoted;
+                        }
+                    }
+                },
+                State::Quoted => {
+                    match character {
+                        _ if character == dialect.quotation => state = State::QuoteInQuoted,
+                        '\n' => {
+                            *line += 1;
+                            field.push(character);
+                        }
+                        _ => field.push(character)
+                    }
+                },
+                State::QuoteInQuoted => {
+                    match character {
+                        _ if character == dialect.quotation => { // Escaped quotation mark.
+                            field.push(character);
+                            state = State::Quoted;
+                        }
+                        _ if character == dialect.delimiter => {
+                            record.push(std::mem::take(&mut field));
+                            state = State::FieldStart;
+                        }
+                        '\r' | '\n' => {
+                            Self::skip_line_feed(source, character);
+                            break;
+                        }
+                        _ if character.is_whitespace() => {
+                            spaces.push(character);
+                            state = State::AfterQuoted;
+                        }
+                        _ => {
+                            field.push(character);
+                            state = State::Unquoted;
+                        }
+                    }
+                },
+                State::AfterQuoted => {
+                    match character {
+                        _ if character == dialect.delimiter => {
+                            spaces.clear();
+                            record.push(std::mem::take(&mut field));
+                            state = State::FieldStart;
+                        }
+                        '\r' | '\n' => {
+                            Self::skip_line_feed(source, character);
+                            break;
+                        }
+                        _ if character.is_whitespace() => spaces.push(character),
+                        _ => { // Keep other characters, so no data is lost.
+                            field.push_str(&std::mem::take(&mut spaces));
+                            field.push(character);
+                            state = State::Unquoted;
+                        }
+                    }
+                }
             }
-            result.push(&row[lower..upper]);
         }
-        Ok(result)
+        *line += 1;
+        record.push(field);
+        Some(record)
+    }
+
+    // Shortened text of a row, for use in error messages.
+    fn snippet (values: &[String], dialect: &Dialect) -> String {
+        let row = values.join(&dialect.delimiter.to_string());
+        if row.chars().count() > SNIPPET_LENGTH {
+            return row.chars().take(SNIPPET_LENGTH).collect::<String>() + "\u{2026}"
+        }
+        row
+    }
+
+    // Consumes the line feed of a CRLF line break.
+    fn skip_line_feed (source: &mut Peekable<Chars<'_>>, character: char) {
+        if character == '\r' && source.peek() == Some(&'\n') {
+            source.next();
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)] // Tests fail on errors.
+mod tests
+{
+    use super::*;
+
+    fn records (text: &str) -> Vec<Vec<String>> {
+        let mut source = text.chars().peekable();
+        let mut line = 1;
+        std::iter::from_fn(|| Decoder::next(&mut source, &Dialect::default(), &mut line)).collect()
+    }
+
+    #[test]
+    fn reads_line_breaks_in_quoted_fields () {
+        let mut source = "a,\"one\ntwo\"\nb,c".chars().peekable();
+        let mut line = 1;
+        assert_eq!(Decoder::next(&mut source, &Dialect::default(), &mut line), Some(vec![String::from("a"), String::from("one\ntwo")]));
+        assert_eq!(line, 3);
+        assert_eq!(Decoder::next(&mut source, &Dialect::default(), &mut line), Some(vec![String::from("b"), String::from("c")]));
+    }
+
+    #[test]
+    fn reads_doubled_quotation_marks () {
+        assert_eq!(records("\"say \"\"hi\"\"\",x"), vec![vec![String::from("say \"hi\""), String::from("x")]]);
+    }
+
+    #[test]
+    fn reads_crlf_line_breaks () {
+        assert_eq!(records("a,b\r\n1,\"2\"\r\n"), vec![vec![String::from("a"), String::from("b")], vec![String::from("1"), String::from("2")]]);
+    }
+
+    #[test]
+    fn keeps_characters_after_closing_quote () {
+        assert_eq!(records("\"a\"  ,\"b\" c,\"d\"e"), vec![vec![String::from("a"), String::from("b c"), String::from("de")]]);
+    }
+
+    #[test]
+    fn discards_byte_order_mark () {
+        let path = std::env::temp_dir().join(format!("bitcoder-bom-{}.csv", std::process::id()));
+        std::fs::write(&path, "\u{feff}Name,Y\nx,1\n").unwrap();
+        let (mut variables, mut rows, mut report) = (Vec::new(), 0, LoadReport::default());
+        let result = Decoder::load(&path.display().to_string(), &Dialect::default(), "", &mut variables, &mut rows, &mut report);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(report.header, vec![String::from("Name"), String::from("Y")]);
+        assert_eq!(rows, 1);
     }
 }
@@ -0,0 +1,188 @@
+/*
+    Describes how a CSV file is written: which character separates fields, which character quotes
+    them and if the first row holds variable names. A dialect can be guessed (sniffed) from the
+    first rows of a file and then be overridden by the user.
+*/
+
+pub const DELIMITERS: [(char, &str); 4] = [(',', "Comma"), (';', "Semicolon"), ('\t', "Tab"), ('|', "Pipe")];
+pub const QUOTATIONS: [(char, &str); 2] = [('"', "Double quote"), ('\'', "Single quote")];
+
+pub const SAMPLE_ROWS: usize = 20; // Number of rows to look at when sniffing.
+
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Clone, Copy)]
+pub struct Dialect
+{
+    pub delimiter: char,  // Separates fields.
+    pub quotation: char,  // Encloses fields that contain delimiters, line breaks or quotation marks.
+    pub has_header: bool  // If first row contains variable names.
+}
+
+impl Default for Dialect
+{
+    fn default () -> Self {
+        Self {
+            delimiter: ',',
+            quotation: '"',
+            has_header: true
+        }
+    }
+}
+
+impl Dialect
+{
+    // Guesses the dialect from the first rows of a file, falls back to default (RFC 4180) values.
+    pub fn sniff (sample: &[String]) -> Self {
+        let rows: Vec<&str> = sample.iter().map(String::as_str).filter(|r| !r.trim().is_empty()).take(SAMPLE_ROWS).collect();
+        let mut dialect = Self::default();
+        if rows.is_empty() {
+            return dialect;
+        }
+        dialect.quotation = Self::sniff_quotation(&rows);
+        dialect.delimiter = Self::sniff_delimiter(&rows, dialect.quotation);
+        dialect.has_header = Self::sniff_header(rows[0], dialect);
+        dialect
+    }
+
+    pub fn delimiter_name (&self) -> &'static str {
+        DELIMITERS.iter().find(|d| d.0 == self.delimiter).map_or("Other", |d| d.1)
+    }
+
+    pub fn quotation_name (&self) -> &'static str {
+        QUOTATIONS.iter().find(|q| q.0 == self.quotation).map_or("Other", |q| q.1)
+    }
+
+    // The quotation mark is the candidate most often found at the start of a field.
+    fn sniff_quotation (rows: &[&str]) -> char {
+        let mut best = (QUOTATIONS[0].0, 0);
+        for (quotation, _) in QUOTATIONS {
+            let mut count = 0;
+            for row in rows {
+                let mut previous = None;
+                for character in row.chars() {
+                    if character == quotation && previous.is_none_or(|p| DELIMITERS.iter().any(|d| d.0 == p)) {
+                        count += 1;
+                    }
+                    previous = Some(character);
+                }
+            }
+            if count > best.1 {
+                best = (quotation, count);
+            }
+        }
+        best.0
+    }
+
+    // The delimiter is the candidate that occurs (outside quotation) the same number of times on
+    // every row. If several do, the most frequent one wins. If none do, the most frequent one wins.
+    // Semicolons win over commas that are all between digits, as these are decimal commas.
+    fn sniff_delimiter (rows: &[&str], quotation: char) -> char {
+        // Least number of times a delimiter occurs on a row, and if it occurs that often on every row.
+        let occurrences = |delimiter: char| {
+            let counts: Vec<usize> = rows.iter().map(|r| Self::count_outside(r, delimiter, quotation)).collect();
+            let minimum = counts.iter().copied().min().unwrap_or(0);
+            (minimum, counts.iter().all(|c| *c == minimum))
+        };
+        if let (1.., true) = occurrences(';') && Self::has_decimal_commas(rows, quotation) {
+            return ';'
+        }
+        let mut best = (DELIMITERS[0].0, true, 0);
+        for (delimiter, _) in DELIMITERS {
+            let (minimum, consistent) = occurrences(delimiter);
+            if minimum == 0 {
+                continue;
+            }
+            if (consistent && !best.1) || (consistent == best.1 && minimum > best.2) || best.2 == 0 {
+                best = (delimiter, consistent, minimum);
+            }
+        }
+        best.0
+    }
+
+    // A header is assumed if no value in the first row is empty or a number.
+    fn sniff_header (row: &str, dialect: Self) -> bool {
+        row.split(dialect.delimiter)
+            .map(|v| v.trim().trim_matches(dialect.quotation).trim())
+            .all(|v| !v.is_empty() && v.replace(',', ".").parse::<f32>().is_err())
+    }
+
+    // If there are commas (outside quotation), and all of them are between digits.
+    fn has_decimal_commas (rows: &[&str], quotation: char) -> bool {
+        let mut commas = 0;
+        for row in rows {
+            let characters: Vec<char> = row.chars().collect();
+            let mut quote = false; // If within quotation.
+            for (position, character) in characters.iter().enumerate() {
+                if *character == quotation {
+                    quote = !quote;
+                } else if *character == ',' && !quote {
+                    let digit = |p: Option<usize>| p.and_then(|p| characters.get(p)).is_some_and(char::is_ascii_digit);
+                    if !digit(position.checked_sub(1)) || !digit(Some(position + 1)) {
+                        return false
+                    }
+                    commas += 1;
+                }
+            }
+        }
+        commas > 0
+    }
+
+    fn count_outside (row: &str, delimiter: char, quotation: char) -> usize {
+        let mut quote = false; // If within quotation.
+        let mut count = 0;
+        for character in row.chars() {
+            if character == quotation {
+                quote = !quote;
+            } else if character == delimiter && !quote {
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn sniff (rows: &[&str]) -> Dialect {
+        Dialect::sniff(&rows.iter().map(|r| (*r).to_string()).collect::<Vec<String>>())
+    }
+
+    #[test]
+    fn sniffs_commas_and_header () {
+        let dialect = sniff(&["Age,Color,Y", "10,red,1", "30,green,0"]);
+        assert_eq!(dialect.delimiter, ',');
+        assert!(dialect.has_header);
+    }
+
+    #[test]
+    fn sniffs_semicolons_with_decimal_commas () {
+        let dialect = sniff(&["1,5;2,5;0", "3,25;4;1"]);
+        assert_eq!(dialect.delimiter, ';');
+        assert!(!dialect.has_header);
+    }
+
+    #[test]
+    fn sniffs_tabs_and_pipes () {
+        assert_eq!(sniff(&["a\tb\tc", "1\t2\t3"]).delimiter, '\t');
+        assert_eq!(sniff(&["a|b", "1|2"]).delimiter, '|');
+    }
+
+    #[test]
+    fn ignores_delimiters_in_quoted_fields () {
+        let dialect = sniff(&["'a;b','c;d'", "'1;2','3;4'"]);
+        assert_eq!(dialect.quotation, '\'');
+        assert_eq!(dialect.delimiter, ',');
+    }
+
+    #[test]
+    fn sniffs_no_header_if_first_row_has_numbers () {
+        assert!(!sniff(&["1,red,0", "2,blue,1"]).has_header);
+    }
+
+    #[test]
+    fn falls_back_to_default_without_rows () {
+        assert!(sniff(&["", "  "]) == Dialect::default());
+    }
+}
@@ -6,47 +6,64 @@
 use crate::models::variable::Variable;
 use std::fs::File;
 use std::io::Write;
+use std::path::{
+    Path,
+    PathBuf
+};
 
 pub struct Encoder;
 impl Encoder 
 {
-    pub fn save (path: &str, variables: &[Variable], outcome: &Variable, rows: usize) -> Result<(), &'static str> {
-        if let Some(desktop) = dirs::desktop_dir() {
-            let original = std::path::PathBuf::from(&path);
-            let mut path = desktop.join(original.file_name().unwrap_or_default());
-            let mut data = String::new();
-            path.set_extension("bitcoder");
-            if let Ok(mut file) = File::create(path) {
-                // Write variable names within quotation and comma-separated.
+    // Output file is named as the input file with extension .bitcoder, in the given folder or on the desktop.
+    pub fn default_path (input: &str, folder: Option<&Path>) -> Result<PathBuf, &'static str> {
+        let folder = match folder {
+            Some(folder) if folder.is_dir() => folder.to_path_buf(),
+            _ => dirs::desktop_dir().ok_or("There is no desktop folder to save to. Use \u{201c}Save as\u{2026}\u{201d} to choose where to save the file.")?
+        };
+        let mut path = folder.join(PathBuf::from(input).file_name().unwrap_or_default());
+        path.set_extension("bitcoder");
+        Ok(path)
+    }
+
+    // Rows left out by the settings of any variable are not written.
+    pub fn save (path: &Path, variables: &[Variable], outcome: &Variable, rows: usize) -> Result<(), &'static str> {
+        if variables.iter().any(|v| v.problem().is_some()) {
+            return Err("Some variables can not be written as they are set (see the message on their card).");
+        }
+        let mut data = String::new();
+        if let Ok(mut file) = File::create(path) {
+            // Write variable names within quotation and comma-separated.
+            data.clear();
+            for variable in variables {
+                for name in variable.bits() {
+                    data.push_str(format!("\"{name}\",").as_str());
+                }
+            }
+            data.push_str(format!("\"{}\"", outcome.name()).as_str());
+            if file.write_all(data.as_bytes()).is_err() {
+                return Err("Error when writing to file.");
+            }
+            // Write clustered variable values as bit strings, end with outcome value.
+            for index in 0..rows {
+                if variables.iter().any(|v| v.drops(index)) {
+                    continue;
+                }
                 data.clear();
+                data.push('\n');
                 for variable in variables {
-                    for name in variable.density().keys() {
-                        data.push_str(format!("\"{name}\",").as_str());
-                    }
+                    let bits: String = variable.vector_of(index)
+                        .iter()
+                        .map(|b| if b.1 { "1"} else {"0"})
+                        .collect();
+                    data.push_str(bits.as_str());
                 }
-                data.push_str(format!("\"{}\"", outcome.name()).as_str());
+                data.push_str(format!(",{}", outcome.value_at(index)).as_str());
                 if file.write_all(data.as_bytes()).is_err() {
                     return Err("Error when writing to file.");
                 }
-                // Write clustered variable values as bit strings, end with outcome value.
-                for index in 0..rows {
-                    data.clear();
-                    data.push('\n');
-                    for variable in variables {
-                        let bits: String = variable.vector_of(index)
-                            .iter()
-                            .map(|b| if b.1 { "1"} else {"0"})
-                            .collect();
-                        data.push_str(bits.as_str());
-                    }
-                    data.push_str(format!(",{}", outcome.value_at(index)).as_str());
-                    if file.write_all(data.as_bytes()).is_err() {
-                        return Err("Error when writing to file.");
-                    }
-                }
-            } else {
-                return Err("File could not be opened for writing. Is it open somewhere else?");
             }
+        } else {
+            return Err("File could not be opened for writing. Is it open somewhere else?");
         }
         Ok(())
     }
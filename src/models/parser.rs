@@ -1,20 +1,63 @@
 /*
     A state machine for parsing expression for categorizing values. Expression are on the form:
 
-    <expressionSequence> :: <expression>[,<expression>...]
-    <expression> :: {<stringExpression>|<numberExpression}
+    <expressionSequence> :: {<expression>[,<expression>...]|<setExpression>[,<setExpression>...]|<patternExpression>[,<patternExpression>...]|<automaticExpression>}
+    <expression> :: {<stringExpression>|<numberExpression>|<boundExpression>|<bracketExpression>} [as {nameLiteral|stringLiteral}]
+    <setExpression> :: "{" stringLiteral[,stringLiteral...] "}" as {nameLiteral|stringLiteral}
+    <patternExpression> :: {"match","like"} stringLiteral [as {nameLiteral|stringLiteral}]
     <stringExpression> :: {<minimumOperator>|stringLiteral} to {<maximumOperator>|stringLiteral}
     <numberExpression> :: {<minimumOperator>|numberLiteral} to {<maximumOperator>|numberLiteral}
+    <boundExpression> :: {{">",">="} <lowerValue> [to [{"<","<="}] <upperValue>]|{"<","<="} <upperValue>}
+    <bracketExpression> :: {"[","("} <lowerValue> , <upperValue> {"]",")"}
+    <lowerValue> :: {<minimumOperator>|stringLiteral|numberLiteral}
+    <upperValue> :: {<maximumOperator>|stringLiteral|numberLiteral}
     <minimumOperator> :: {"min","low","lowest"}
     <maximumOperator> :: {"max","high","highest"}
     <stringLiteral> :: any sequence of characters with in quotation marks.
     <numberLiteral> :: any sequence of characters parseable as an f32.
+    <nameLiteral> :: any sequence of characters without whitespace, quotation marks, commas, brackets, braces, '<', '>' or '|'.
+    <automaticExpression> :: {<quantileExpression>|<binExpression>|<widthExpression>|<stepExpression>|<optimalExpression>}
+    <quantileExpression> :: {"auto","quantiles"} countLiteral
+    <binExpression> :: "bins" countLiteral
+    <widthExpression> :: "width" positiveLiteral
+    <stepExpression> :: "step" positiveLiteral from numberLiteral
+    <optimalExpression> :: "optimal" countLiteral
+    <countLiteral> :: any sequence of characters parseable as a positive integer.
+    <positiveLiteral> :: any sequence of characters parseable as an f32 greater than zero.
     
     Example:
     low to 3.5, 3.5 to max
     "A" to "C", "D" to "E", "F" to max
+    low to 18 as child, 18 to 65 as "adult", 65 to max as senior
+    < 0, [0, 10), >= 10 to < 20, >= 20
+    {"Norway","Sweden","Denmark"} as Nordic, {"Spain","Italy"} as South
+    match "^E1[0-4]" as diabetes, like "*steel*"
+    quantiles 4
+    step 10 from 20
+    optimal 30
 
-    Lower range value is inclusive (>=).
+    An automatic expression creates clusters from the values themselves and must be used alone:
+    - quantiles: (at most) the given number of clusters with about as many values in each.
+    - bins: the given number of clusters of equal width, from minimum to maximum value.
+    - width: clusters of the given width, at multiples of the width, covering minimum to maximum value.
+    - step: clusters of the given width, from the given value up to maximum value.
+    - optimal: clusters that best predict the outcome, with at least the given number of values in each.
+
+    A name given with 'as' is used for the bit variable instead of the range values, and must be
+    unique. Names can not contain quotation marks, line breaks or '|', and 'Other' and 'Missing' are
+    reserved for values outside all clusters and missing values.
+
+    A set groups values in any order, and a value can only be in one set. Sets and ranges can not
+    be combined in one expression.
+
+    A pattern is either a regular expression ('match') or a wildcard pattern ('like', where '*' is
+    any text and '?' is any character, regardless of case). A value belongs to the first pattern it
+    matches. Patterns can not be combined with ranges or sets in one expression.
+
+    Range values are inclusive, unless given as exclusive by '>' or '<', or by '(' or ')' around the
+    range. A range with an operator on its lower value only goes to max, and one with an operator on
+    its upper value only starts from min. Ranges can not overlap, but may share a value, which then
+    belongs to the first range that includes it (so a range of only that value must come first).
     Whitespace between tokens is discarded.
 
 */
@@ -34,7 +77,15 @@ enum State
     MaximumOrValue,
     MaximumOrString,
     MaximumOrNumber,
-    Delimiter
+    LabelOrDelimiter,
+    Delimiter,
+    SetValue,
+    MatchPattern,
+    LikePattern,
+    SetDelimiter,
+    Label,
+    LabelName,
+    Finished
 }
 
 #[derive(PartialEq)]
@@ -44,36 +95,100 @@ pub enum Token
     Maximum,
     Range,
     String {value: String},
-    Number {value: f32}
+    Number {value: f32},
+    Exclusive,
+    Set {values: Vec<String>},
+    Match {pattern: String},
+    Like {pattern: String},
+    Label {value: String},
+    Quantiles {count: usize},
+    Bins {count: usize},
+    Width {width: f32},
+    Step {step: f32, start: f32},
+    Optimal {size: usize}
+}
+
+impl Token
+{
+    // If token creates clusters from the values themselves, rather than from given ranges.
+    pub const fn is_automatic (&self) -> bool {
+        matches!(self, Token::Quantiles {..} | Token::Bins {..} | Token::Width {..} | Token::Step {..} | Token::Optimal {..})
+    }
 }
 
 pub struct Parser;
 impl Parser
 {
-    // #[allow(clippy::too_many_lines)]
+    #[allow(clippy::too_many_lines)]
     pub fn parse (input: &str) -> Result<Vec<Token>, String> {
         let mut expect = State::MinimumOrValue;
         let mut source = input.chars().peekable();
         let mut tokens: Vec<Token> = Vec::new();
+        let mut members: Vec<String> = Vec::new(); // Values of the set being parsed.
+        let mut seen: Vec<String> = Vec::new();    // Values of all sets parsed so far.
+        let mut operator = false; // If an operator ('<', '>' etc.) was given for the next value.
+        let mut open = false;     // If range was started by '>', and goes to max unless an upper value is given.
+        let mut bracket = false;  // If within brackets.
         while let Some(text) = Self::next(&mut source) {
             match expect {
                 State::MinimumOrValue => {
                     match text.to_lowercase().as_str() {
                         "min" | "lowest"  | "low"  => { 
                             tokens.push(Token::Minimum);
+                            operator = false;
                             expect = State::ValueRange;
                         },
                         "max" | "highest" | "high" | "to" => {
                             return Err(format!("Unexpected keyword '{text}' (string, number or 'min' expected)."))
                         }
+                        "{" | "[" | "(" | ">" | ">=" | "<" | "<=" | "match" | "like" | "auto" | "quantiles" | "bins" | "width" | "step" | "optimal" if operator || bracket => {
+                            return Err(format!("Unexpected '{text}' (string, number or 'min' expected)."))
+                        }
+                        "{" => {
+                            members.clear();
+                            expect = State::SetValue;
+                        }
+                        "match" => expect = State::MatchPattern,
+                        "like" => expect = State::LikePattern,
+                        "[" | "(" => {
+                            if text == "(" {
+                                tokens.push(Token::Exclusive);
+                            }
+                            bracket = true;
+                        }
+                        ">" | ">=" => {
+                            if text == ">" {
+                                tokens.push(Token::Exclusive);
+                            }
+                            operator = true;
+                            open = true;
+                        }
+                        "<" | "<=" => {
+                            tokens.push(Token::Minimum);
+                            tokens.push(Token::Range);
+                            if text == "<" {
+                                tokens.push(Token::Exclusive);
+                            }
+                            operator = true;
+                            expect = State::MaximumOrValue;
+                        }
+                        "auto" | "quantiles" | "bins" | "width" | "step" | "optimal" => {
+                            if !tokens.is_empty() {
+                                return Err(format!("Keyword '{text}' must be used alone."))
+                            }
+                            tokens.push(Self::automatic(text.to_lowercase().as_str(), &mut source)?);
+                            expect = State::Finished;
+                        }
                         _ => {
                             if text.starts_with('@') { // A quoted string.
                                 tokens.push(Token::String { value: text.strip_prefix('@').unwrap_or_default().to_string() });
+                                operator = false;
                                 expect = State::StringRange;
                                 continue;
                             }
                             if let Ok(number) = text.parse::<f32>() {
                                 tokens.push(Token::Number { value: number });
+                                operator = false;
                                 expect = State::NumberRange;
                                 continue;
                             }
@@ -82,14 +197,29 @@ impl Parser
                     }
                 },
                 State::ValueRange | State::StringRange | State::NumberRange => {
+                    let upper = match expect {
+                        State::StringRange => State::MaximumOrString,
+                        State::NumberRange => State::MaximumOrNumber,
+                        _ => State::MaximumOrValue
+                    };
                     match text.to_lowercase().as_str() {
-                        "to" => { 
+                        "to" if !bracket => { 
+                            tokens.push(Token::Range);
+                            open = false;
+                            expect = upper;
+                        }
+                        "," if bracket => {
                             tokens.push(Token::Range);
-                            expect = match expect {
-                                State::StringRange => State::MaximumOrString,
-                                State::NumberRange => State::MaximumOrNumber,
-                                _ => State::MaximumOrValue
-                            };
+                            expect = upper;
+                        }
+                        "," | "as" if open => { // Range started by '>' goes to max.
+                            tokens.push(Token::Range);
+                            tokens.push(Token::Maximum);
+                            open = false;
+                            expect = if text == "," { State::MinimumOrValue } else { State::LabelName };
+                        }
+                        _ if bracket => {
+                            return Err(String::from("Separate range values within brackets with ','."))
                         }
                         _ => {
                             return Err(String::from("Separate range values with keyword ' to '."))
@@ -100,18 +230,29 @@ impl Parser
                     match text.to_lowercase().as_str() {
                         "max" | "highest" | "high"  => { 
                             tokens.push(Token::Maximum);
-                            expect = State::Delimiter;
+                            operator = false;
+                            expect = State::LabelOrDelimiter;
                         },
                         "min" | "lowest" | "low" | "to" => {
                             return Err(format!("Unexpected keyword '{text}' (string, number or 'max' expected)."))
                         }
+                        "<" | "<=" if !operator && !bracket => {
+                            if text == "<" {
+                                tokens.push(Token::Exclusive);
+                            }
+                            operator = true;
+                        }
+                        "[" | "(" | "]" | ")" | ">" | ">=" | "<" | "<=" => {
+                            return Err(format!("Unexpected '{text}' (string, number or 'max' expected)."))
+                        }
                         _ => {
                             if text.starts_with('@') { // A quoted string.
                                 if expect == State::MaximumOrNumber {
                                     return Err(String::from("Both lower and upper range must be strings."))
                                 }
                                 tokens.push(Token::String { value: text.strip_prefix('@').unwrap_or_default().to_string() });
-                                expect = State::Delimiter;
+                                operator = false;
+                                expect = State::LabelOrDelimiter;
                                 continue;
                             }
                             if let Ok(number) = text.parse::<f32>() {
@@ -119,22 +260,157 @@ impl Parser
                                     return Err(String::from("Both lower and upper range must be valid numbers."))
                                 }
                                 tokens.push(Token::Number { value: number });
-                                expect = State::Delimiter;
+                                operator = false;
+                                expect = State::LabelOrDelimiter;
                                 continue;
                             }
                             return Err(format!("Unexpected upper range value '{text}' (a string or a valid number expected)."))
                         }
                     }
                 }
-                State::Delimiter => {
+                State::LabelOrDelimiter if bracket => {
                     match text.as_str() {
+                        "]" => bracket = false,
+                        ")" => {
+                            tokens.insert(tokens.len() - 1, Token::Exclusive); // Before upper value.
+                            bracket = false;
+                        }
+                         _  => return Err(format!("Unexpected text '{text}' (closing ']' or ')' expected)."))
+                    }
+                }
+                State::LabelOrDelimiter | State::Delimiter => {
+                    match text.to_lowercase().as_str() {
                         "," => expect = State::MinimumOrValue, // Start over, parsing next expression.
+                        "as" if expect == State::LabelOrDelimiter => expect = State::LabelName,
                          _  => return Err(format!("Unexpected text '{text}' (delimiter ',' expected)."))
                     }
                 }
+                State::SetValue => {
+                    match text.strip_prefix('@') {
+                        Some(value) => {
+                            if seen.iter().any(|v| v == value) && !members.iter().any(|v| v == value) {
+                                return Err(format!("Value \u{201c}{value}\u{201d} is in more than one set."))
+                            }
+                            if !members.iter().any(|v| v == value) {
+                                members.push(value.to_string());
+                                seen.push(value.to_string());
+                            }
+                            expect = State::SetDelimiter;
+                        }
+                        None if text == "}" => return Err(String::from("A set must have at least one value.")),
+                        None => return Err(format!("Unexpected text '{text}' (a string expected within braces)."))
+                    }
+                }
+                State::MatchPattern | State::LikePattern => {
+                    let Some(pattern) = text.strip_prefix('@') else {
+                        return Err(format!("Unexpected text '{text}' (a pattern within quotation marks expected)."))
+                    };
+                    if expect == State::MatchPattern {
+                        if regex::Regex::new(pattern).is_err() {
+                            return Err(format!("Pattern \u{201c}{pattern}\u{201d} is not a valid regular expression."))
+                        }
+                        tokens.push(Token::Match { pattern: pattern.to_string() });
+                    } else {
+                        tokens.push(Token::Like { pattern: pattern.to_string() });
+                    }
+                    expect = State::LabelOrDelimiter;
+                }
+                State::SetDelimiter => {
+                    match text.as_str() {
+                        "," => expect = State::SetValue,
+                        "}" => {
+                            tokens.push(Token::Set { values: std::mem::take(&mut members) });
+                            expect = State::Label;
+                        }
+                         _  => return Err(format!("Unexpected text '{text}' (delimiter ',' or closing '}}' expected)."))
+                    }
+                }
+                State::Label => {
+                    match text.to_lowercase().as_str() {
+                        "as" => expect = State::LabelName,
+                         _   => return Err(String::from("Name the set with keyword ' as '."))
+                    }
+                }
+                State::LabelName => {
+                    let name = text.strip_prefix('@').unwrap_or(&text);
+                    if name.trim().is_empty() || matches!(name, "," | "{" | "}" | "[" | "]" | "(" | ")" | "<" | "<=" | ">" | ">=") {
+                        return Err(format!("Unexpected text '{text}' (a name expected after 'as')."))
+                    }
+                    if name.chars().any(|c| c == '"' || c == '|' || c.is_control()) {
+                        return Err(format!("Name \u{201c}{name}\u{201d} can not contain quotation marks, line breaks or '|'."))
+                    }
+                    let reserved = match name {
+                        "Other" => Some("values outside all clusters"),
+                        "Missing" => Some("missing values"),
+                        _ => None
+                    };
+                    if let Some(reserved) = reserved {
+                        return Err(format!("Name \u{201c}{name}\u{201d} is reserved for {reserved}."))
+                    }
+                    if tokens.iter().any(|t| matches!(t, Token::Label { value } if value == name)) {
+                        return Err(format!("Name \u{201c}{name}\u{201d} is used more than once."))
+                    }
+                    tokens.push(Token::Label { value: name.to_string() });
+                    expect = State::Delimiter;
+                }
+                State::Finished => {
+                    return Err(format!("Unexpected text '{text}' (automatic clusters must be used alone)."))
+                }
+            }
+        }
+        if open && matches!(expect, State::ValueRange | State::StringRange | State::NumberRange) { // Range started by '>' goes to max.
+            tokens.push(Token::Range);
+            tokens.push(Token::Maximum);
+        }
+        if operator {
+            return Err(String::from("Type a value after the operator."))
+        }
+        match expect {
+            _ if bracket => Err(String::from("Close the range with ']' or ')'.")),
+            State::SetValue | State::SetDelimiter => Err(String::from("Close the set with '}'.")),
+            State::Label => Err(String::from("Name the set with keyword ' as '.")),
+            State::LabelName => Err(String::from("Type a name after 'as'.")),
+            State::MatchPattern | State::LikePattern => Err(String::from("Type a pattern within quotation marks.")),
+            _ => Ok(tokens)
+        }
+    }
+
+    // Parses what follows the keyword of an automatic expression.
+    fn automatic (keyword: &str, source: &mut Peekable<Chars<'_>>) -> Result<Token, String> {
+        let positive = |text: Option<String>, what: &str| -> Result<f32, String> {
+            match text.as_deref().map(str::parse::<f32>) {
+                Some(Ok(number)) if number > 0.0 && number.is_finite() => Ok(number),
+                Some(_) => Err(format!("Unexpected text '{}' after '{keyword}' ({what} greater than zero expected).", text.unwrap_or_default())),
+                None => Err(format!("Type the {what} after '{keyword}'."))
+            }
+        };
+        match keyword {
+            "width" => Ok(Token::Width { width: positive(Self::next(source), "width")? }),
+            "step" => {
+                let step = positive(Self::next(source), "step")?;
+                if Self::next(source).is_none_or(|t| t.to_lowercase() != "from") {
+                    return Err(String::from("Give the first value of steps with keyword ' from '."))
+                }
+                match Self::next(source).map(|t| t.parse::<f32>()) {
+                    Some(Ok(start)) if start.is_finite() => Ok(Token::Step { step, start }),
+                    _ => Err(String::from("Type a valid number after 'from'."))
+                }
+            }
+            _ => {
+                let what = if keyword == "optimal" { "number of values per cluster" } else { "number of clusters" };
+                let count = positive(Self::next(source), what)?;
+                if count.fract() != 0.0 {
+                    return Err(format!("The {what} after '{keyword}' must be a whole number."))
+                }
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Positive and whole.
+                let count = count as usize;
+                match keyword {
+                    "bins" => Ok(Token::Bins { count }),
+                    "optimal" => Ok(Token::Optimal { size: count }),
+                    _ => Ok(Token::Quantiles { count })
+                }
             }
         }
-        Ok(tokens)
     }
 
     pub fn next (input: &mut Peekable<Chars<'_>>) -> Option<String> {
@@ -159,16 +435,24 @@ impl Parser
                         break;
                     }
                 },
-                ',' => {
+                ',' | '{' | '}' | '[' | ']' | '(' | ')' => {
                     token.push(character);
                     if quote { 
                         continue;
                     }
                     break;
                 },
+                '<' | '>' if !quote => {
+                    token.push(character);
+                    if input.peek() == Some(&'=') {
+                        token.push('=');
+                        input.next();
+                    }
+                    break;
+                },
                 _ => {
                     token.push(character);
-                    if let Some(preview) = input.peek() && *preview == ',' {
+                    if !quote && let Some(preview) = input.peek() && matches!(*preview, ',' | '{' | '}' | '[' | ']' | '(' | ')' | '<' | '>') {
                         break;
                     }
                 }
@@ -181,3 +465,53 @@ impl Parser
     }
 
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn number (value: f32) -> Token {
+        Token::Number { value }
+    }
+
+    #[test]
+    fn parses_exclusive_operators_and_brackets () {
+        assert!(Parser::parse("> 0 to 10").unwrap_or_default() == vec![Token::Exclusive, number(0.0), Token::Range, number(10.0)]);
+        assert!(Parser::parse(">= 20").unwrap_or_default() == vec![number(20.0), Token::Range, Token::Maximum]);
+        assert!(Parser::parse("< 0").unwrap_or_default() == vec![Token::Minimum, Token::Range, Token::Exclusive, number(0.0)]);
+        assert!(Parser::parse("[0, 10)").unwrap_or_default() == vec![number(0.0), Token::Range, Token::Exclusive, number(10.0)]);
+        assert!(Parser::parse("(0, 10]").unwrap_or_default() == vec![Token::Exclusive, number(0.0), Token::Range, number(10.0)]);
+    }
+
+    #[test]
+    fn parses_sets_with_names () {
+        let tokens = Parser::parse("{\"a\", \"b\"} as first, {\"c\"} as \"second one\"").unwrap_or_default();
+        assert!(tokens == vec![
+            Token::Set { values: vec![String::from("a"), String::from("b")] },
+            Token::Label { value: String::from("first") },
+            Token::Set { values: vec![String::from("c")] },
+            Token::Label { value: String::from("second one") }
+        ]);
+    }
+
+    #[test]
+    fn refuses_value_in_more_than_one_set () {
+        assert!(Parser::parse("{\"a\", \"b\"} as x, {\"b\"} as y").is_err());
+    }
+
+    #[test]
+    fn refuses_reserved_invalid_and_repeated_names () {
+        assert!(Parser::parse("0 to 1 as Other").is_err());
+        assert!(Parser::parse("0 to 1 as Missing").is_err());
+        assert!(Parser::parse("0 to 1 as \"a|b\"").is_err());
+        assert!(Parser::parse("0 to 1 as a, 1 to 2 as a").is_err());
+        assert!(Parser::parse("0 to 1 as a, 1 to 2 as b").is_ok());
+    }
+
+    #[test]
+    fn checks_regular_expressions_only () {
+        assert!(Parser::parse("match \"([\"").is_err());
+        assert!(Parser::parse("match \"^E1[0-4]\" as diabetes, like \"*(steel*\"").is_ok());
+    }
+}
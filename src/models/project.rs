@@ -0,0 +1,79 @@
+/*
+    A project holds everything needed to redo the work on an input file: where the file is, how it
+    is written (dialect), which variable is the outcome and the settings (cards) of all other
+    variables. Projects are saved as RON files and can be shared, as the input file is referred to
+    relative to the project file when it is in the same folder (or below).
+*/
+
+use crate::models::card::Card;
+use crate::models::dialect::Dialect;
+use std::path::{
+    Path,
+    PathBuf
+};
+
+pub const EXTENSION: &str = "bitproject";
+const VERSION: u32 = 1; // Increase when the format changes, and convert older versions in load.
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Project
+{
+    pub version: u32,     // Version of the file format.
+    pub input: String,    // Path of input file, relative to the project file if possible.
+    pub dialect: Dialect, // How the input file is written.
+    pub outcome: String,  // Name of outcome variable, as in header of input file.
+    pub cards: Vec<Card>  // Settings of all other variables, in the same order as in the input file.
+}
+
+impl Project
+{
+    pub fn new (input: &str, dialect: Dialect, outcome: &str, cards: Vec<Card>) -> Self {
+        Self {
+            version: VERSION,
+            input: input.to_string(),
+            dialect,
+            outcome: outcome.to_string(),
+            cards
+        }
+    }
+
+    pub fn load (path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Project file cannot be read ({e})."))?;
+        let mut project: Self = ron::from_str(&text).map_err(|e| format!("Project file is not valid ({e})."))?;
+        if project.version == 0 || project.version > VERSION {
+            return Err(format!("Project file has version {}, but only versions 1 to {VERSION} are supported.", project.version))
+        }
+        // Input file is relative to the project file, unless absolute.
+        if let Some(folder) = path.parent() && Path::new(&project.input).is_relative() {
+            project.input = folder.join(&project.input).display().to_string();
+        }
+        Ok(project)
+    }
+
+    pub fn save (&mut self, path: &Path) -> Result<(), String> {
+        let input = PathBuf::from(&self.input);
+        if let Some(folder) = path.parent() && let Ok(relative) = input.strip_prefix(folder) {
+            self.input = relative.display().to_string();
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string());
+        self.input = input.display().to_string();
+        std::fs::write(path, text?).map_err(|e| format!("Project file cannot be written ({e})."))
+    }
+
+    // Checks that variable names of project match the header of the input file.
+    pub fn validate (&self, header: &[String]) -> Result<(), String> {
+        if !header.contains(&self.outcome) {
+            return Err(format!("Outcome variable \u{201c}{}\u{201d} of the project is not in the file.", self.outcome))
+        }
+        let names: Vec<&String> = header.iter().filter(|n| **n != self.outcome).collect();
+        if names.len() != self.cards.len() {
+            return Err(format!("The project has {} variables, but the file has {}.", self.cards.len() + 1, header.len()))
+        }
+        for (card, name) in self.cards.iter().zip(names) {
+            if !card.name.is_empty() && card.name != *name {
+                return Err(format!("Variable \u{201c}{}\u{201d} of the project does not match \u{201c}{name}\u{201d} in the file.", card.name))
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+/*
+    Descriptive statistics of a list of numbers, such as the values of a numeric variable or the outcome
+    values of the rows of a bit variable. Quartiles interpolate linearly between the sorted values.
+*/
+
+#[derive(Default)]
+pub struct Statistics
+{
+    pub count: usize,    // Number of values.
+    pub distinct: usize, // Number of unique values.
+    pub mean: f32,       // Arithmetic mean.
+    pub median: f32,     // Middle value, or mean of both middle values if count is even.
+    pub first: f32,      // First quartile (a quarter of values is lower).
+    pub third: f32,      // Third quartile (a quarter of values is higher).
+    pub deviation: f32,  // Sample standard deviation (zero if fewer than two values).
+    pub skewness: f32    // Third standardized moment (zero if all values are equal).
+}
+
+impl Statistics
+{
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)] // Counts are far below the precision of f64, results are within range of values.
+    pub fn of (values: &[f32]) -> Self {
+        if values.is_empty() {
+            return Self::default()
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f32::total_cmp);
+        let count = sorted.len() as f64;
+        let mean = sorted.iter().map(|v| f64::from(*v)).sum::<f64>() / count;
+        let moment = |power: i32| sorted.iter().map(|v| (f64::from(*v) - mean).powi(power)).sum::<f64>();
+        let (squares, cubes) = (moment(2), moment(3));
+        let deviation = if sorted.len() > 1 { (squares / (count - 1.0)).sqrt() } else { 0.0 };
+        let skewness = if squares > 0.0 { (cubes / count) / (squares / count).powf(1.5) } else { 0.0 };
+        let mut distinct = sorted.clone();
+        distinct.dedup_by(|a, b| a.total_cmp(b).is_eq());
+        Self {
+            count: sorted.len(),
+            distinct: distinct.len(),
+            mean: mean as f32,
+            median: Self::quantile(&sorted, 0.5),
+            first: Self::quantile(&sorted, 0.25),
+            third: Self::quantile(&sorted, 0.75),
+            deviation: deviation as f32,
+            skewness: skewness as f32
+        }
+    }
+
+    // Value below which the given share of sorted values lies, interpolated between neighbouring values.
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Position is within 0 and count.
+    fn quantile (sorted: &[f32], share: f32) -> f32 {
+        let position = share * (sorted.len() - 1) as f32;
+        let lower = position.floor() as usize;
+        let upper = (lower + 1).min(sorted.len() - 1);
+        sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f32)
+    }
+}
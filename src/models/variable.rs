@@ -2,10 +2,15 @@
     Represents a variable, holding a vector of data points as either a strings, f32s or "missing".
 */
  
+use crate::models::binning::Binning;
 use crate::models::parser::Token;
+use crate::models::statistics::Statistics;
 use std::cmp::Ordering;
 
-use std::collections::HashMap;
+use std::collections::{
+    BTreeMap,
+    BTreeSet
+};
 use std::hash::{
     Hash,
     Hasher
@@ -15,6 +20,9 @@ use std::fmt::{
     Formatter
 };
 
+const MAXIMUM_CLUSTERS: usize = 1000; // Limit for automatic clusters of equal width.
+const FREQUENCY_BINS:   usize = 40;   // Number of bins of equal width for frequencies of numeric values.
+
 #[derive(Default, Clone, PartialEq)]
 pub enum Value 
 {
@@ -107,42 +115,240 @@ impl Value
 
 }
 
-#[derive(Default, PartialEq)]
+#[derive(Default, PartialEq, Clone)]
 pub struct Range 
 {
     lower: Value, 
-    upper: Value
+    upper: Value,
+    label: String,            // Name of bit variable, instead of range values (if not empty).
+    is_lower_exclusive: bool, // If lower value itself is outside range.
+    is_upper_exclusive: bool  // If upper value itself is outside range.
+}
+
+impl Range
+{
+    // Ranges overlap if they have more in common than a shared lower or upper value (which belongs to the first range).
+    fn overlaps (&self, other: &Range) -> bool {
+        self.lower < other.upper && other.lower < self.upper
+    }
+}
+
+#[derive(Default, PartialEq)]
+pub struct Set 
+{
+    name: String,       // Name of set, used in bit variable name.
+    values: Vec<String> // Values in set, in any order.
+}
+
+pub struct Pattern
+{
+    regex: regex::Regex, // Compiled pattern.
+    text: String,        // Pattern as written in expression.
+    is_wildcard: bool,   // If written with 'like' (rather than 'match').
+    label: String        // Name of bit variable, instead of pattern text (if not empty).
+}
+
+impl PartialEq for Pattern
+{
+    fn eq (&self, other: &Self) -> bool {
+        self.text == other.text && self.is_wildcard == other.is_wildcard && self.label == other.label
+    }
+}
+
+impl Pattern
+{
+    fn new (text: &str, is_wildcard: bool) -> Result<Self, &'static str> {
+        let source = if is_wildcard {
+            let mut source = String::from("(?i)^");
+            for character in text.chars() {
+                match character {
+                    '*' => source.push_str(".*"),
+                    '?' => source.push('.'),
+                     _  => source.push_str(&regex::escape(&character.to_string()))
+                }
+            }
+            source + "$"
+        } else {
+            text.to_string()
+        };
+        let regex = regex::Regex::new(&source).map_err(|_| "Pattern is not a valid regular expression.")?;
+        Ok(Self { regex, text: text.to_string(), is_wildcard, label: String::new() })
+    }
 }
 
 #[derive(Default, PartialEq)]
 pub enum Mapping 
 {
-    Cluster {clusters: Vec<Range>}, // Values are grouped into clusters, as described by an expression.
-    #[default]                      // Recode is the default ...
-    Recode                          // .. and means every unique value is a group.
+    Cluster {clusters: Vec<Range>},   // Values are grouped into clusters, as described by an expression.
+    Set {sets: Vec<Set>},             // Values are grouped into named sets, as described by an expression.
+    Pattern {patterns: Vec<Pattern>}, // Values are grouped by the first pattern they match, as described by an expression.
+    #[default]                        // Recode is the default ...
+    Recode                            // .. and means every unique value is a group.
+}
+
+#[derive(Default, PartialEq, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub enum BitOrder
+{
+    #[default]
+    Natural,     // Value order if recoded, cluster order if clustered by an expression.
+    Frequency,   // Most frequent bit first.
+    Alphabetical // By bit variable name.
+}
+
+impl Display for BitOrder
+{
+    fn fmt (&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BitOrder::Natural => write!(formatter, "Value or cluster order"),
+            BitOrder::Frequency => write!(formatter, "Most frequent first"),
+            BitOrder::Alphabetical => write!(formatter, "Alphabetical")
+        }
+    }
+}
+
+#[derive(Default, PartialEq, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub enum OtherValues
+{
+    #[default]
+    Bit,    // Values outside all clusters get a bit variable of their own (Other).
+    Drop,   // Rows with a value outside all clusters are left out.
+    Error,  // Values outside all clusters are not allowed.
+    Nearest // Values outside all clusters belong to the nearest range.
+}
+
+impl Display for OtherValues
+{
+    fn fmt (&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OtherValues::Bit => write!(formatter, "Other values get own bit"),
+            OtherValues::Drop => write!(formatter, "Other values drop row"),
+            OtherValues::Error => write!(formatter, "Other values not allowed"),
+            OtherValues::Nearest => write!(formatter, "Other values to nearest")
+        }
+    }
+}
+
+#[derive(Default, PartialEq, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub enum MissingValues
+{
+    #[default]
+    Zeros,  // Missing values give all bits zero.
+    Bit,    // Missing values get a bit variable of their own (Missing).
+    Drop,   // Rows with a missing value are left out.
+    Impute  // Missing values are replaced by a value (see Imputation).
+}
+
+impl Display for MissingValues
+{
+    fn fmt (&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MissingValues::Zeros => write!(formatter, "Missing values as zeros"),
+            MissingValues::Bit => write!(formatter, "Missing values get own bit"),
+            MissingValues::Drop => write!(formatter, "Missing values drop row"),
+            MissingValues::Impute => write!(formatter, "Missing values imputed")
+        }
+    }
+}
+
+#[derive(Default, PartialEq, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub enum Imputation
+{
+    #[default]
+    Cluster, // A value of the most frequent cluster.
+    Mean,    // Mean of numeric values.
+    Median,  // Median of numeric values.
+    Mode,    // Most frequent value.
+    Constant // A given value.
+}
+
+impl Display for Imputation
+{
+    fn fmt (&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Imputation::Cluster => write!(formatter, "Impute most frequent cluster"),
+            Imputation::Mean => write!(formatter, "Impute mean"),
+            Imputation::Median => write!(formatter, "Impute median"),
+            Imputation::Mode => write!(formatter, "Impute most frequent value"),
+            Imputation::Constant => write!(formatter, "Impute constant")
+        }
+    }
+}
+
+#[derive(Default, PartialEq, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub enum RareValues
+{
+    #[default]
+    Keep,    // Every unique value gets a bit variable of its own.
+    Count,   // Values with fewer observations than threshold are merged into Other (if recoded).
+    Percent  // Values with a smaller percentage of observations than threshold are merged into Other (if recoded).
+}
+
+impl Display for RareValues
+{
+    fn fmt (&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RareValues::Keep => write!(formatter, "Rare values kept"),
+            RareValues::Count => write!(formatter, "Rare values (count) to Other"),
+            RareValues::Percent => write!(formatter, "Rare values (percent) to Other")
+        }
+    }
+}
+
+#[derive(Default, PartialEq, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub enum Conversion
+{
+    #[default]
+    Missing,                // Value that is not a number is missing.
+    Number { number: f32 }, // Value that is not a number is replaced by a number.
+    Bit                     // Value that is not a number gets a bit variable of its own.
+}
+
+impl Display for Conversion
+{
+    fn fmt (&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Conversion::Missing => write!(formatter, "As missing"),
+            Conversion::Number {..} => write!(formatter, "As number"),
+            Conversion::Bit => write!(formatter, "As own bit")
+        }
+    }
 }
 
 #[derive(Default)]
 pub struct Histogram
 {
-    density: HashMap<String,usize>, // Frequency of unique values, key is bit variable name (bitname).
-    missing: usize,                 // Number of missing values.
-    minimum: Value,                 // Minimum value (String or Number).
-    maximum: Value,                 // Maximum value (String or Number).
-
+    density: BTreeMap<String,usize>, // Frequency of unique values, key is bit variable name (bitname).
+    bits: Vec<String>,               // Bit variable names in output order.
+    missing: usize,                  // Number of missing values.
+    others: usize,                   // Number of values outside all clusters.
+    minimum: Value,                  // Minimum value (String or Number).
+    maximum: Value,                  // Maximum value (String or Number).
+    frequencies: Vec<usize>          // Number of numeric values in each bin between minimum and maximum.
 }
 
 
 #[derive(Default)]
 pub struct Variable
 {
-    name: String,           // Identifier of this variable
-    values: Vec<Value>,     // List of actual values (Number, String or None). 
-    backup: Vec<Value>,     // Clone of string values when converting to number (and back).
-    histogram: Histogram,   // Statistics, including table of frequence.
-    mapping: Mapping,       // Values are either grouped as one cluster per unique value, or into clusters through an expression.
-    is_included: bool,      // If included in output or not.
-    is_numeric: bool        // If all values are numbers.
+    name: String,              // Identifier of this variable
+    values: Vec<Value>,        // List of actual values (Number, String or None). 
+    backup: Vec<Value>,        // Clone of string values when converting to number (and back).
+    histogram: Histogram,      // Statistics, including table of frequence.
+    statistics: Statistics,    // Descriptive statistics of numeric values, computed on conversion.
+    failures: BTreeMap<String,usize>,          // Frequency of strings that could not be converted to numbers.
+    conversions: BTreeMap<String,Conversion>,  // What such strings are converted to instead, missing if not listed.
+    mapping: Mapping,          // Values are either grouped as one cluster per unique value, or into clusters through an expression.
+    order: BitOrder,           // Order of bit variables in output.
+    on_other: OtherValues,     // What to do with values outside all clusters.
+    on_missing: MissingValues, // What to do with missing values.
+    imputation: Imputation,    // How missing values are replaced, if imputed.
+    constant: String,          // Value that replaces missing values, if imputed by constant.
+    imputed: Vec<usize>,       // Positions of replaced missing values, to restore them (like backup).
+    rare: RareValues,          // What to do with rare values, if recoded.
+    threshold: f32,            // Number or percentage of observations below which a value is rare.
+    merged: BTreeSet<String>,  // Bit variables of rare values, merged into Other.
+    is_included: bool,         // If included in output or not.
+    is_numeric: bool           // If all values are numbers.
 }
 
 impl Variable
@@ -153,7 +359,19 @@ impl Variable
             values: Vec::new(),
             backup: Vec::new(),
             histogram: Histogram::default(),
+            statistics: Statistics::default(),
+            failures: BTreeMap::new(),
+            conversions: BTreeMap::new(),
             mapping: Mapping::default(),
+            order: BitOrder::default(),
+            on_other: OtherValues::default(),
+            on_missing: MissingValues::default(),
+            imputation: Imputation::default(),
+            constant: String::new(),
+            imputed: Vec::new(),
+            rare: RareValues::default(),
+            threshold: 0.0,
+            merged: BTreeSet::new(),
             is_included: true,
             is_numeric: false
         }
@@ -161,27 +379,40 @@ impl Variable
 
     pub fn set_recoded (&mut self) {
         self.mapping = Mapping::Recode;
-        self.histogram = Histogram::default();
-        for value in &mut self.values {
-            Self::recalculate(&mut self.histogram, &self.mapping, &self.name, value);
-        }
+        self.rebuild();
     }
 
     pub fn set_cluster (&mut self) {
         self.mapping = Mapping::Cluster { clusters: Vec::new() };
-        self.histogram = Histogram::default();
-        for value in &mut self.values {
-            Self::recalculate(&mut self.histogram, &self.mapping, &self.name, value);
-        }
+        self.rebuild();
     }
 
-    pub fn use_ranges (&mut self, tokens: &[Token]) -> Result<(), &'static str> {
+    // Outcome variable is used only by expressions that cluster values to predict it.
+    pub fn use_ranges (&mut self, tokens: &[Token], outcome: &Variable) -> Result<(), &'static str> {
+        match tokens {
+            [Token::Quantiles { count }] => return self.use_clusters(self.quantiles(*count)),
+            [Token::Bins { count }] => return self.use_clusters(self.bins(*count)?),
+            [Token::Width { width }] => {
+                let (minimum, _) = self.bounds()?;
+                return self.use_clusters(self.steps((minimum / width).floor() * width, *width)?)
+            }
+            [Token::Step { step, start }] => return self.use_clusters(self.steps(*start, *step)?),
+            [Token::Optimal { size }] => return self.use_clusters(self.optimal(*size, outcome)?),
+            _ => {}
+        }
+        if tokens.iter().any(|t| matches!(t, Token::Set {..})) {
+            return self.use_sets(tokens);
+        }
+        if tokens.iter().any(|t| matches!(t, Token::Match {..} | Token::Like {..})) {
+            return self.use_patterns(tokens);
+        }
         let mut ranges = Vec::<Range>::new();
-        let mut tokens = tokens.iter();
+        let mut tokens = tokens.iter().peekable();
         loop {
-            let mut range = Range::default();
+            let mut range = Range { is_lower_exclusive: tokens.next_if_eq(&&Token::Exclusive).is_some(), ..Default::default() };
             let value1st = tokens.next();
             let operator = tokens.next();
+            range.is_upper_exclusive = tokens.next_if_eq(&&Token::Exclusive).is_some();
             let value2nd = tokens.next();
             if  value1st.is_none() {
                 break;
@@ -201,14 +432,28 @@ impl Variable
                 Some(Token::Maximum) => range.upper = self.histogram.maximum.clone(),
                 _ => return Err("Type a value for the upper range.")
             }
+            if let Some(Token::Label { value }) = tokens.peek() {
+                range.label.clone_from(value);
+                tokens.next();
+            }
             if range.lower > range.upper {
                 return Err("Lower value must be less than or equal to the upper value.")
             }
-            if !ranges.is_empty() && range.lower < ranges.last().unwrap_or(&Range::default()).upper {
-                return Err("Lower value must be greater than or equal to the previous upper value.")
+            if range.lower == range.upper && (range.is_lower_exclusive || range.is_upper_exclusive) {
+                return Err("Range is empty, as its only value is excluded.")
+            }
+            if ranges.iter().any(|r| r.overlaps(&range)) {
+                return Err("Ranges can not overlap (but may share a lower or upper value).")
+            }
+            if range.lower == range.upper && Self::get_range(&range.lower, &ranges).is_some() {
+                return Err("Range is empty, as its only value belongs to an earlier range.")
             }
             ranges.push(range);
         }
+        self.use_clusters(ranges)
+    }
+
+    fn use_clusters (&mut self, ranges: Vec<Range>) -> Result<(), &'static str> {
         let all_numbers = ranges.iter().all(|r| matches!(r.lower, Value::Number{..}) && matches!(r.upper, Value::Number{..}));
         let all_strings = ranges.iter().all(|r| matches!(r.lower, Value::String{..}) && matches!(r.upper, Value::String{..}));
         if self.is_numeric && !all_numbers || !self.is_numeric && !all_strings {
@@ -217,52 +462,443 @@ impl Variable
         let clusters = !ranges.is_empty();
         self.mapping = Mapping::Cluster { clusters: ranges };
         if clusters {
-            self.histogram = Histogram::default();
-            for value in &mut self.values {
-                Self::recalculate(&mut self.histogram, &self.mapping, &self.name, value);
+            self.rebuild();
+        }
+        Ok(())
+    }
+
+    fn use_sets (&mut self, tokens: &[Token]) -> Result<(), &'static str> {
+        if self.is_numeric {
+            return Err("Sets can only be used with string values.")
+        }
+        let mut sets = Vec::<Set>::new();
+        for pair in tokens.chunks(2) {
+            match pair {
+                [Token::Set { values }, Token::Label { value }] => sets.push(Set { name: value.clone(), values: values.clone() }),
+                _ => return Err("Sets and ranges can not be combined in one expression.")
+            }
+        }
+        self.mapping = Mapping::Set { sets };
+        self.rebuild();
+        Ok(())
+    }
+
+    fn use_patterns (&mut self, tokens: &[Token]) -> Result<(), &'static str> {
+        if self.is_numeric {
+            return Err("Patterns can only be used with string values.")
+        }
+        let mut patterns = Vec::<Pattern>::new();
+        let mut tokens = tokens.iter().peekable();
+        while let Some(token) = tokens.next() {
+            let mut pattern = match token {
+                Token::Match { pattern } => Pattern::new(pattern, false)?,
+                Token::Like { pattern } => Pattern::new(pattern, true)?,
+                _ => return Err("Patterns can not be combined with ranges or sets in one expression.")
+            };
+            if let Some(Token::Label { value }) = tokens.peek() {
+                pattern.label.clone_from(value);
+                tokens.next();
+            }
+            // Without a name, the text of the pattern names the bit variable, so it is checked like a name (see Parser).
+            if pattern.label.is_empty() && pattern.text.chars().any(|c| c == '"' || c == '|' || c.is_control()) {
+                return Err("A pattern with quotation marks, line breaks or '|' needs a name (given with ' as ').")
+            }
+            if pattern.label.is_empty() && matches!(pattern.text.as_str(), "Other" | "Missing") {
+                return Err("A pattern named by its text can not be \u{201c}Other\u{201d} or \u{201c}Missing\u{201d}, as these are reserved.")
             }
+            if patterns.iter().any(|p| Self::name_from_pattern(&self.name, p) == Self::name_from_pattern(&self.name, &pattern)) {
+                return Err("Patterns must have unique names (or texts, if not named).")
+            }
+            patterns.push(pattern);
         }
+        self.mapping = Mapping::Pattern { patterns };
+        self.rebuild();
         Ok(())
     }
 
+    // Patterns (as written) that no value belongs to, as they match no value or only values of earlier patterns.
+    // None if excluded, as no value belongs to any bit variable then.
+    pub fn unmatched (&self) -> Vec<&str> {
+        if !self.is_included {
+            return Vec::new()
+        }
+        match &self.mapping {
+            Mapping::Pattern { patterns } => patterns.iter()
+                .filter(|p| !self.histogram.density.contains_key(&Self::name_from_pattern(&self.name, p)))
+                .map(|p| p.text.as_str())
+                .collect(),
+            _ => Vec::new()
+        }
+    }
+
+    // Ranges of current clusters written as an expression, that can be used instead of an automatic one.
+    pub fn expression (&self) -> String {
+        match &self.mapping {
+            Mapping::Recode => String::new(),
+            Mapping::Cluster { clusters } => Self::expression_of(clusters),
+            Mapping::Pattern { patterns } => patterns.iter()
+                .map(|p| {
+                    let pattern = format!("{} \"{}\"", if p.is_wildcard {"like"} else {"match"}, p.text);
+                    if p.label.is_empty() { pattern } else { format!("{pattern} as \"{}\"", p.label) }
+                })
+                .collect::<Vec<String>>()
+                .join(", "),
+            Mapping::Set { sets } => sets.iter()
+                .map(|s| format!("{{{}}} as \"{}\"", s.values.iter().map(|v| format!("\"{v}\"")).collect::<Vec<String>>().join(", "), s.name))
+                .collect::<Vec<String>>()
+                .join(", ")
+        }
+    }
+
+    // Expression of ranges after moving a boundary (as in boundaries) to another value. None if the value
+    // is not between the boundaries before and after it, as ranges would change order or become empty.
+    pub fn move_boundary (&self, from: f32, to: f32) -> Option<String> {
+        let Mapping::Cluster { clusters } = &self.mapping else {
+            return None
+        };
+        let boundaries = self.boundaries();
+        let position = boundaries.iter().position(|b| b.total_cmp(&from).is_eq())?;
+        let before = position.checked_sub(1).and_then(|p| boundaries.get(p));
+        let after = boundaries.get(position + 1);
+        if before.is_some_and(|b| to <= *b) || after.is_some_and(|a| to >= *a) {
+            return None
+        }
+        let (from, to) = (Value::Number { number: from }, Value::Number { number: to });
+        let mut ranges = clusters.clone();
+        for range in &mut ranges {
+            if range.lower == from {
+                range.lower = to.clone();
+            }
+            if range.upper == from {
+                range.upper = to.clone();
+            }
+        }
+        Some(Self::expression_of(&ranges))
+    }
+
+    // Expression of ranges after cutting the range that holds a value in two (without labels). Without
+    // ranges, values are cut in two from minimum to maximum. None if no range holds the value.
+    pub fn add_boundary (&self, at: f32) -> Option<String> {
+        let at = Value::Number { number: at };
+        let mut ranges = match &self.mapping {
+            Mapping::Cluster { clusters } => clusters.clone(),
+            Mapping::Recode => Vec::new(),
+            Mapping::Set {..} | Mapping::Pattern {..} => return None
+        };
+        if ranges.is_empty() {
+            if self.histogram.minimum < at && at < self.histogram.maximum {
+                ranges.push(Range { lower: self.histogram.minimum.clone(), upper: at.clone(), ..Default::default() });
+                ranges.push(Range { lower: at, upper: self.histogram.maximum.clone(), ..Default::default() });
+                return Some(Self::expression_of(&ranges))
+            }
+            return None
+        }
+        let position = ranges.iter().position(|r| r.lower < at && at < r.upper)?;
+        let range = ranges.remove(position);
+        ranges.insert(position, Range { lower: at.clone(), upper: range.upper, is_upper_exclusive: range.is_upper_exclusive, ..Default::default() });
+        ranges.insert(position, Range { lower: range.lower, upper: at, is_lower_exclusive: range.is_lower_exclusive, ..Default::default() });
+        Some(Self::expression_of(&ranges))
+    }
+
+    // Expression of ranges after joining the two ranges that share a boundary into one (without label).
+    // None if no two ranges share it.
+    pub fn remove_boundary (&self, at: f32) -> Option<String> {
+        let Mapping::Cluster { clusters } = &self.mapping else {
+            return None
+        };
+        let at = Value::Number { number: at };
+        let first = clusters.iter().position(|r| r.upper == at)?;
+        let second = clusters.iter().position(|r| r.lower == at)?;
+        if first == second {
+            return None
+        }
+        let mut ranges = clusters.clone();
+        ranges[first] = Range {
+            lower: clusters[first].lower.clone(),
+            upper: clusters[second].upper.clone(),
+            is_lower_exclusive: clusters[first].is_lower_exclusive,
+            is_upper_exclusive: clusters[second].is_upper_exclusive,
+            ..Default::default()
+        };
+        ranges.remove(second);
+        Some(Self::expression_of(&ranges))
+    }
+
+    // Associated function instead of method, as ranges may differ from those of the variable.
+    fn expression_of (ranges: &[Range]) -> String {
+        let literal = |value: &Value| match value {
+            Value::Number { number } => number.to_string(),
+            Value::String { string } => format!("\"{string}\""),
+            Value::None => String::new()
+        };
+        ranges.iter()
+            .map(|r| {
+                let range = if r.is_lower_exclusive || r.is_upper_exclusive {
+                    format!("{}{}, {}{}", if r.is_lower_exclusive {"("} else {"["}, literal(&r.lower), literal(&r.upper), if r.is_upper_exclusive {")"} else {"]"})
+                } else {
+                    format!("{} to {}", literal(&r.lower), literal(&r.upper))
+                };
+                if r.label.is_empty() { range } else { format!("{range} as \"{}\"", r.label) }
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    // Splits sorted values into (at most) the given number of clusters with about as many values in each.
+    // Equal values are never split, so clusters may be fewer (or less even) if values are repeated.
+    fn quantiles (&self, count: usize) -> Vec<Range> {
+        // Strings kept as own bit in numeric variables are not clustered.
+        let mut values: Vec<&Value> = self.values.iter().filter(|v| if self.is_numeric { matches!(v, Value::Number {..}) } else { **v != Value::None }).collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let mut ranges = Vec::<Range>::new();
+        let mut start  = 0; // Position of first value in current cluster.
+        for (position, value) in values.iter().enumerate() {
+            let next = values.get(position + 1);
+            if next.is_some_and(|n| n == value) {
+                continue;
+            }
+            // Size is recalculated for every cluster, to even out clusters made larger by repeated values.
+            let remaining = count.saturating_sub(ranges.len()).max(1);
+            let size = (values.len() - start + remaining / 2) / remaining;
+            if next.is_none() || position + 1 - start >= size {
+                ranges.push(Range { lower: values[start].clone(), upper: (*value).clone(), ..Default::default() });
+                start = position + 1;
+            }
+        }
+        ranges
+    }
+
+    // Splits the span from minimum to maximum value into the given number of clusters of equal width.
+    fn bins (&self, count: usize) -> Result<Vec<Range>, &'static str> {
+        if count > MAXIMUM_CLUSTERS {
+            return Err("Too many clusters, use fewer bins.")
+        }
+        let (minimum, maximum) = self.bounds()?;
+        #[allow(clippy::cast_precision_loss)] // Exact, as count is small.
+        let parts = count as f32;
+        let mut ranges = Vec::<Range>::new();
+        let mut steps = 0.0; // Counted as f32, to calculate bounds without accumulating errors.
+        for _ in 0..count {
+            steps += 1.0;
+            let lower = ranges.last().map_or(Value::Number { number: minimum }, |r| r.upper.clone());
+            let upper = if ranges.len() + 1 == count { maximum } else { minimum + (maximum - minimum) * steps / parts };
+            ranges.push(Range { lower, upper: Value::Number { number: upper }, ..Default::default() });
+            if minimum >= maximum {
+                break;
+            }
+        }
+        Ok(ranges)
+    }
+
+    // Clusters of the given width, from the given start until the maximum value is covered.
+    fn steps (&self, start: f32, width: f32) -> Result<Vec<Range>, &'static str> {
+        let (_, maximum) = self.bounds()?;
+        if start > maximum {
+            return Err("First value of steps must be less than or equal to the maximum value.")
+        }
+        let mut ranges = Vec::<Range>::new();
+        let mut steps = 0.0; // Counted as f32, to calculate bounds without accumulating errors.
+        loop {
+            steps += 1.0;
+            let lower = ranges.last().map_or(Value::Number { number: start }, |r| r.upper.clone());
+            let upper = start + width * steps;
+            ranges.push(Range { lower, upper: Value::Number { number: upper }, ..Default::default() });
+            if upper >= maximum {
+                break;
+            }
+            if ranges.len() >= MAXIMUM_CLUSTERS {
+                return Err("Too many clusters, use a larger width.")
+            }
+        }
+        Ok(ranges)
+    }
+
+    // Clusters with cuts chosen to best predict the outcome, each with at least the given number of values.
+    fn optimal (&self, size: usize, outcome: &Variable) -> Result<Vec<Range>, &'static str> {
+        let (minimum, maximum) = self.bounds()?;
+        let mut pairs: Vec<(f32, f32)> = self.values.iter().zip(&outcome.values).filter_map(|pair| match pair {
+            (Value::Number { number: value }, Value::Number { number: result }) => Some((*value, *result)),
+            _ => None
+        }).collect();
+        if pairs.len() < size {
+            return Err("There are fewer values than the number of values per cluster.")
+        }
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut lower = minimum;
+        let mut ranges = Vec::<Range>::new();
+        for cut in Binning::cuts(&pairs, size).into_iter().chain([maximum]) {
+            ranges.push(Range { lower: Value::Number { number: lower }, upper: Value::Number { number: cut }, ..Default::default() });
+            lower = cut;
+        }
+        Ok(ranges)
+    }
+
+    // Minimum and maximum value of a numeric variable, for automatic clusters.
+    fn bounds (&self) -> Result<(f32, f32), &'static str> {
+        if !self.is_numeric {
+            return Err("These automatic clusters can only be used with numeric values.")
+        }
+        match (&self.histogram.minimum, &self.histogram.maximum) {
+            (Value::Number { number: minimum }, Value::Number { number: maximum }) => Ok((*minimum, *maximum)),
+            _ => Err("There are no values to cluster.")
+        }
+    }
+
     pub fn add_value (&mut self, value: &str) {
         let value = Value::new(value);
-        Self::recalculate(&mut self.histogram, &self.mapping, &self.name, &value);
+        Self::recalculate(&mut self.histogram, &self.mapping, (self.on_other, self.on_missing), &self.name, self.is_numeric, &value);
         self.values.push(value);
     }
 
     pub fn as_numbers (&mut self) {
+        self.restore();
         self.is_numeric = true;
         self.backup = self.values.clone();
-        self.histogram = Histogram::default();
-        for value in &mut self.values {
+        self.convert();
+    }
+
+    // Converts backup to numbers, and strings that are not numbers as set by conversions.
+    fn convert (&mut self) {
+        self.failures.clear();
+        for (value, original) in self.values.iter_mut().zip(&self.backup) {
+            value.clone_from(original);
             value.as_number();
-            Self::recalculate(&mut self.histogram, &self.mapping, &self.name, value);
+            if let Value::String { string } = original && *value == Value::None {
+                *self.failures.entry(string.clone()).or_insert(0) += 1;
+                *value = match self.conversions.get(string) {
+                    Some(Conversion::Number { number }) => Value::Number { number: *number },
+                    Some(Conversion::Bit) => original.clone(),
+                    Some(Conversion::Missing) | None => Value::None
+                };
+            }
         }
+        let numbers: Vec<f32> = self.values.iter().filter_map(|v| if let Value::Number { number } = v { Some(*number) } else { None }).collect();
+        self.statistics = Statistics::of(&numbers);
+        self.rebuild();
     }
 
     pub fn as_strings (&mut self) {
         self.is_numeric = false;
+        self.imputed.clear(); // Backup has no replaced values.
         self.values = self.backup.clone();
         self.backup.clear();
-        self.histogram = Histogram::default();
-        for value in &mut self.values {
-            Self::recalculate(&mut self.histogram, &self.mapping, &self.name, value);
-        }
+        self.statistics = Statistics::default();
+        self.failures.clear();
+        self.rebuild();
     }
 
     pub fn name (&self) -> &str {
         self.name.as_str()
     }
 
-    pub fn density (&self) -> &HashMap<String,usize> {
+    pub fn density (&self) -> &BTreeMap<String,usize> {
         &self.histogram.density
     }
 
+    pub fn bits (&self) -> &[String] {
+        &self.histogram.bits
+    }
+
+    pub fn set_order (&mut self, order: BitOrder) {
+        self.order = order;
+        self.arrange();
+    }
+
+    // Sorts bit variable names according to the order policy, ties are broken alphabetically.
+    pub fn arrange (&mut self) {
+        let density = &self.histogram.density;
+        let mut bits: Vec<String> = density.keys().cloned().collect(); // Alphabetical, as keys are ordered.
+        match (self.order, &self.mapping) {
+            (BitOrder::Alphabetical, _) => {},
+            (BitOrder::Frequency, _) => {
+                bits.sort_by(|a, b| density.get(b).cmp(&density.get(a)));
+            },
+            (BitOrder::Natural, Mapping::Recode) => {
+                let mut values = BTreeMap::<String,&Value>::new();
+                for value in self.values.iter().filter(|v| **v != Value::None) {
+                    values.entry(Self::name_from_value(&self.name, value)).or_insert(value);
+                }
+                bits.sort_by(|a, b| match (values.get(a), values.get(b)) {
+                    (Some(a), Some(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+                    (a, b) => a.is_none().cmp(&b.is_none()) // Missing is last.
+                });
+            },
+            (BitOrder::Natural, Mapping::Cluster { clusters }) => {
+                let names: Vec<String> = clusters.iter().map(|r| Self::name_from_range(&self.name, r)).collect();
+                bits.sort_by_key(|b| names.iter().position(|n| n == b).unwrap_or(names.len())); // Other is last.
+            }
+            (BitOrder::Natural, Mapping::Set { sets }) => {
+                let names: Vec<String> = sets.iter().map(|s| Self::name_from_set(&self.name, s)).collect();
+                bits.sort_by_key(|b| names.iter().position(|n| n == b).unwrap_or(names.len())); // Other is last.
+            }
+            (BitOrder::Natural, Mapping::Pattern { patterns }) => {
+                let names: Vec<String> = patterns.iter().map(|p| Self::name_from_pattern(&self.name, p)).collect();
+                bits.sort_by_key(|b| names.iter().position(|n| n == b).unwrap_or(names.len())); // Other is last.
+            }
+        }
+        self.histogram.bits = bits;
+    }
+
     pub fn missing (&self) -> usize {
         self.histogram.missing
     }
 
+    // Descriptive statistics of numeric values (before imputation), empty if not numeric.
+    pub fn statistics (&self) -> &Statistics {
+        &self.statistics
+    }
+
+    // Frequency of each string that could not be converted to a number, if numeric.
+    pub fn failures (&self) -> &BTreeMap<String,usize> {
+        &self.failures
+    }
+
+    // Sets what strings that are not numbers are converted to, and converts again if numeric.
+    pub fn set_conversions (&mut self, conversions: &BTreeMap<String,Conversion>) {
+        conversions.clone_into(&mut self.conversions);
+        if self.is_numeric {
+            self.restore();
+            self.convert();
+        }
+    }
+
+    // Number of numeric values in each bin of equal width between minimum and maximum (as of last rebuild,
+    // as counting takes a pass over all values).
+    pub fn frequencies (&self) -> &[usize] {
+        &self.histogram.frequencies
+    }
+
+    // Number of numeric values in each of a number of bins of equal width, between minimum and maximum.
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Bin index is within 0 and bins.
+    fn count_bins (&self, bins: usize) -> Vec<usize> {
+        let (Value::Number { number: minimum }, Value::Number { number: maximum }) = (&self.histogram.minimum, &self.histogram.maximum) else {
+            return Vec::new()
+        };
+        let mut counts = vec![0; bins];
+        let width = (maximum - minimum) / bins as f32;
+        for value in &self.values {
+            if let Value::Number { number } = value && bins > 0 {
+                let bin = if width > 0.0 { ((number - minimum) / width) as usize } else { 0 };
+                counts[bin.min(bins - 1)] += 1;
+            }
+        }
+        counts
+    }
+
+    // Numeric lower and upper values of all clusters (if clustered by ranges), ascending and without duplicates.
+    pub fn boundaries (&self) -> Vec<f32> {
+        let Mapping::Cluster { clusters } = &self.mapping else {
+            return Vec::new()
+        };
+        let mut boundaries: Vec<f32> = clusters.iter()
+            .flat_map(|r| [&r.lower, &r.upper])
+            .filter_map(|v| if let Value::Number { number } = v { Some(*number) } else { None })
+            .collect();
+        boundaries.sort_by(f32::total_cmp);
+        boundaries.dedup();
+        boundaries
+    }
+
     pub fn minimum (&self) -> &Value {
         &self.histogram.minimum
     }
@@ -282,10 +918,7 @@ impl Variable
     pub fn set_name (&mut self, name: &str) {
         self.name = name.to_string();
         // Recalculation needed since bit variable names are stored in density map.
-        self.histogram = Histogram::default();
-        for value in &mut self.values {
-            Self::recalculate(&mut self.histogram, &self.mapping, &self.name, value);
-        }
+        self.rebuild();
     }
 
     pub fn include (&mut self) {
@@ -293,10 +926,7 @@ impl Variable
             return;
         }
         self.is_included = true;
-        self.histogram = Histogram::default();
-        for value in &mut self.values {
-            Self::recalculate(&mut self.histogram, &self.mapping, &self.name, value);
-        }
+        self.rebuild();
     }
 
     pub fn exclude (&mut self) {
@@ -309,57 +939,358 @@ impl Variable
 
     pub fn vector_of (&self, index: usize) -> Vec<(String,bool)> {
         let mut bits = Vec::<(String,bool)>::new();
-        let current  = Self::bit_name(&self.mapping, &self.name, &self.values[index]);
-        for key in self.histogram.density.keys() {
-            bits.push((key.clone(), *key == current));
+        let current = self.bit_at(index);
+        for key in &self.histogram.bits {
+            bits.push((key.clone(), current.as_ref() == Some(key)));
         }
         bits
     }
 
+    // Outcome values of the rows of each bit variable, in output order. Rows without a numeric outcome are ignored.
+    pub fn outcomes_by_bit (&self, outcome: &Variable) -> Vec<(&str, Vec<f32>)> {
+        let mut outcomes = BTreeMap::<String,Vec<f32>>::new();
+        for index in 0..self.values.len() {
+            if let Some(bit) = self.bit_at(index) && let Value::Number { number } = outcome.value_at(index) {
+                outcomes.entry(bit).or_default().push(*number);
+            }
+        }
+        self.histogram.bits.iter().map(|b| (b.as_str(), outcomes.remove(b).unwrap_or_default())).collect()
+    }
+
+    // Bit variable of the row at index, if any.
+    fn bit_at (&self, index: usize) -> Option<String> {
+        let value = &self.values[index];
+        let cluster = Self::cluster_of(&self.mapping, &self.name, self.is_numeric, value);
+        Self::bit_name(&self.mapping, (self.on_other, self.on_missing), &self.name, value, cluster)
+            .map(|b| if self.merged.contains(&b) { self.name.clone() + "|Other" } else { b })
+    }
+
+    // If the row at index is left out of the output, due to a missing value or a value outside all clusters.
+    pub fn drops (&self, index: usize) -> bool {
+        let value = &self.values[index];
+        self.is_included && if *value == Value::None {
+            self.on_missing == MissingValues::Drop
+        } else {
+            self.on_other == OtherValues::Drop && Self::cluster_of(&self.mapping, &self.name, self.is_numeric, value).is_none()
+        }
+    }
+
+    // Number of rows left out of the output due to this variable.
+    pub fn dropped (&self) -> usize {
+        let missing = if self.on_missing == MissingValues::Drop { self.histogram.missing } else { 0 };
+        let others = if self.on_other == OtherValues::Drop { self.histogram.others } else { 0 };
+        missing + others
+    }
+
+    // Why variable can not be written to output as it is set, if so.
+    pub fn problem (&self) -> Option<String> {
+        if !self.is_included {
+            return None
+        }
+        if self.on_other == OtherValues::Nearest && matches!(self.mapping, Mapping::Set {..} | Mapping::Pattern {..}) {
+            return Some(String::from("Other values can only be moved to the nearest cluster if clustered by ranges."))
+        }
+        if self.on_other == OtherValues::Error && self.histogram.others > 0 {
+            return Some(format!("{} values are outside all clusters, which is not allowed.", self.histogram.others))
+        }
+        if self.on_missing == MissingValues::Impute && self.histogram.missing > 0 {
+            return Some(String::from(match self.imputation {
+                Imputation::Mean | Imputation::Median if !self.is_numeric => "Only numeric values can be imputed by mean or median.",
+                Imputation::Constant if self.constant.is_empty() => "Type a constant to impute missing values with.",
+                Imputation::Constant => "The constant to impute must be a number, as the values are.",
+                _ => "There are no values to impute missing values from."
+            }))
+        }
+        None
+    }
+
+    pub fn set_other_values (&mut self, other: OtherValues) {
+        self.on_other = other;
+        self.rebuild();
+    }
+
+    pub fn set_missing_values (&mut self, missing: MissingValues) {
+        self.on_missing = missing;
+        self.rebuild();
+    }
+
+    pub fn set_imputation (&mut self, imputation: Imputation, constant: &str) {
+        self.imputation = imputation;
+        self.constant = constant.to_string();
+        self.rebuild();
+    }
+
+    pub fn set_rare_values (&mut self, rare: RareValues, threshold: f32) {
+        self.rare = rare;
+        self.threshold = threshold;
+        self.rebuild();
+    }
+
+    // Number of rare values merged into Other.
+    pub fn merged (&self) -> usize {
+        self.merged.len()
+    }
+
+    // Count below which values are rare, so that merging them into Other leaves at most the given number of bits.
+    #[allow(clippy::cast_precision_loss)] // Counts are far below the precision of f32.
+    pub fn rare_threshold (&self, bits: usize) -> f32 {
+        let mut counts = BTreeMap::<String,usize>::new();
+        for value in self.values.iter().filter(|v| **v != Value::None) {
+            *counts.entry(value.to_string()).or_insert(0) += 1;
+        }
+        let mut counts: Vec<usize> = counts.into_values().collect();
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        // Most frequent values are kept, leaving one bit for Other.
+        counts.get(bits.saturating_sub(1)).map_or(0.0, |c| (c + 1) as f32)
+    }
+
+    // Number of missing values replaced by imputation.
+    pub fn imputed (&self) -> usize {
+        self.imputed.len()
+    }
+
+    // Replaces missing values as set by imputation, after restoring values replaced before.
+    fn impute (&mut self) {
+        self.restore();
+        if self.on_missing != MissingValues::Impute {
+            return;
+        }
+        let mut numbers: Vec<f32> = self.values.iter().filter_map(|v| if let Value::Number { number } = v { Some(*number) } else { None }).collect();
+        let fill = match self.imputation {
+            Imputation::Mean | Imputation::Median if !self.is_numeric || numbers.is_empty() => None,
+            Imputation::Mean => {
+                let sum: f64 = numbers.iter().map(|n| f64::from(*n)).sum();
+                #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)] // Mean lies within the range of f32 values.
+                let mean = (sum / numbers.len() as f64) as f32;
+                Some(Value::Number { number: mean })
+            }
+            Imputation::Median => {
+                numbers.sort_by(f32::total_cmp);
+                let middle = numbers.len() / 2;
+                let median = if numbers.len().is_multiple_of(2) { f32::midpoint(numbers[middle - 1], numbers[middle]) } else { numbers[middle] };
+                Some(Value::Number { number: median })
+            }
+            Imputation::Mode | Imputation::Cluster => {
+                // Count and first position of each value (or cluster), to take the first of equally frequent.
+                let mut counts = BTreeMap::<String,(usize,usize)>::new();
+                for (position, value) in self.values.iter().enumerate().filter(|(_, v)| **v != Value::None) {
+                    let key = if self.imputation == Imputation::Mode { Some(value.to_string()) } else { Self::cluster_of(&self.mapping, &self.name, self.is_numeric, value) };
+                    if let Some(key) = key {
+                        counts.entry(key).or_insert((0, position)).0 += 1;
+                    }
+                }
+                counts.values().max_by_key(|(count, position)| (*count, std::cmp::Reverse(*position))).map(|(_, position)| self.values[*position].clone())
+            }
+            Imputation::Constant => {
+                let mut value = Value::new(&self.constant);
+                if self.is_numeric {
+                    value.as_number();
+                }
+                Some(value).filter(|v| *v != Value::None)
+            }
+        };
+        if let Some(fill) = fill {
+            for (position, value) in self.values.iter_mut().enumerate() {
+                if *value == Value::None {
+                    *value = fill.clone();
+                    self.imputed.push(position);
+                }
+            }
+        }
+    }
+
+    // Sets replaced values back to missing.
+    fn restore (&mut self) {
+        for position in self.imputed.drain(..) {
+            self.values[position] = Value::None;
+        }
+    }
+
+    fn rebuild (&mut self) {
+        self.impute();
+        self.histogram = Histogram::default();
+        for value in &mut self.values {
+            Self::recalculate(&mut self.histogram, &self.mapping, (self.on_other, self.on_missing), &self.name, self.is_numeric, value);
+        }
+        self.histogram.frequencies = self.count_bins(FREQUENCY_BINS);
+        self.merge();
+        self.arrange();
+    }
+
+    // Merges bit variables of rare values into one Other bit, by their counts in density (if recoded).
+    #[allow(clippy::cast_precision_loss)] // Counts are far below the precision of f32.
+    fn merge (&mut self) {
+        self.merged.clear();
+        if !matches!(self.mapping, Mapping::Recode) {
+            return;
+        }
+        let density = &mut self.histogram.density;
+        let missing = self.name.clone() + "|Missing";
+        let total = density.iter().filter(|(b, _)| **b != missing).map(|(_, c)| c).sum::<usize>() as f32;
+        let limit = match self.rare {
+            RareValues::Keep => return,
+            RareValues::Count => self.threshold,
+            RareValues::Percent => self.threshold * total / 100.0
+        };
+        self.merged = density.iter().filter(|(b, c)| **b != missing && (**c as f32) < limit).map(|(b, _)| b.clone()).collect();
+        let count: usize = self.merged.iter().filter_map(|b| density.remove(b)).sum();
+        if count > 0 {
+            density.insert(self.name.clone() + "|Other", count);
+        }
+    }
+
+    // Associated function instead of method to avoid "cannot mutate self twice". 
+    // Bit variable of a value, if it has one, given the cluster it belongs to (see cluster_of).
+    fn bit_name (mapping: &Mapping, rules: (OtherValues, MissingValues), name: &String, value: &Value, cluster: Option<String>) -> Option<String> {
+        if *value == Value::None {
+            return (rules.1 == MissingValues::Bit).then(|| name.to_owned() + "|Missing")
+        }
+        cluster.or_else(|| match rules.0 {
+            OtherValues::Bit => Some(name.to_owned() + "|Other"),
+            OtherValues::Nearest => Self::nearest(mapping, name, value),
+            OtherValues::Drop | OtherValues::Error => None
+        })
+    }
+
     // Associated function instead of method to avoid "cannot mutate self twice". 
-    fn bit_name (mapping: &Mapping, name: &String, value: &Value) -> String {
+    // Bit variable of the cluster a (non-missing) value belongs to, if any.
+    fn cluster_of (mapping: &Mapping, name: &String, is_numeric: bool, value: &Value) -> Option<String> {
         match &mapping {
             Mapping::Recode => {
-                Self::name_from_value(name, value)
+                Some(Self::name_from_value(name, value))
             },
             Mapping::Cluster { clusters } => {
+                // Strings kept in numeric variables (see Conversion) are clusters of their own.
+                if is_numeric && let Value::String {..} = value {
+                    return Some(Self::name_from_value(name, value));
+                }
                 if let Some(range) = Self::get_range(value, clusters) {
-                    return Self::name_from_range(name, range);
+                    return Some(Self::name_from_range(name, range));
+                }
+                None
+            }
+            Mapping::Set { sets } => {
+                if let Value::String { string } = value && let Some(set) = sets.iter().find(|s| s.values.contains(string)) {
+                    return Some(Self::name_from_set(name, set));
+                }
+                None
+            }
+            Mapping::Pattern { patterns } => {
+                if let Value::String { string } = value && let Some(pattern) = patterns.iter().find(|p| p.regex.is_match(string)) {
+                    return Some(Self::name_from_pattern(name, pattern));
                 }
-                name.to_owned() + "|Other"
+                None
             }
         }
     }
 
+    // Associated function instead of method to avoid "cannot mutate self twice". 
+    // Bit variable of the range nearest to a value outside all ranges. Numbers are compared by distance,
+    // strings by order (the range with the highest upper value up to the value, or else the range with the
+    // lowest lower value), regardless of the order in which ranges are written.
+    fn nearest (mapping: &Mapping, name: &String, value: &Value) -> Option<String> {
+        let Mapping::Cluster { clusters } = mapping else {
+            return None
+        };
+        let range = if let Value::Number { number } = value {
+            let distance = |range: &Range| match (&range.lower, &range.upper) {
+                (Value::Number { number: lower }, _) if number < lower => lower - number,
+                (_, Value::Number { number: upper }) if number > upper => number - upper,
+                _ => 0.0
+            };
+            clusters.iter().min_by(|a, b| distance(a).total_cmp(&distance(b)))
+        } else {
+            let by = |bound: fn(&Range) -> &Value| move |a: &&Range, b: &&Range| bound(a).partial_cmp(bound(b)).unwrap_or(Ordering::Equal);
+            clusters.iter().filter(|r| r.upper <= *value).max_by(by(|r| &r.upper))
+                .or_else(|| clusters.iter().min_by(by(|r| &r.lower)))
+        };
+        range.map(|r| Self::name_from_range(name, r))
+    }
+
     // Associated function instead of method to avoid "cannot mutate self twice". 
     fn name_from_value (name: &String, value: &Value) -> String {
         name.to_owned() + "|" + &value.to_string()
     }
 
     // Associated function instead of method to avoid "cannot mutate self twice". 
+    // Numbers are written at full precision (unlike values), so that narrow ranges get names of their own.
     fn name_from_range (name: &String, range: &Range) -> String {
-        name.to_owned() + "|" + &range.lower.to_string() + "|" + &range.upper.to_string()
+        if !range.label.is_empty() {
+            return name.to_owned() + "|" + &range.label
+        }
+        let bound = |value: &Value| if let Value::Number { number } = value { number.to_string() } else { value.to_string() };
+        name.to_owned() + "|" + &bound(&range.lower) + "|" + &bound(&range.upper)
+    }
+
+    // Associated function instead of method to avoid "cannot mutate self twice". 
+    fn name_from_set (name: &String, set: &Set) -> String {
+        name.to_owned() + "|" + &set.name
+    }
+
+    // Associated function instead of method to avoid "cannot mutate self twice". 
+    fn name_from_pattern (name: &String, pattern: &Pattern) -> String {
+        name.to_owned() + "|" + if pattern.label.is_empty() { &pattern.text } else { &pattern.label }
     }
 
     // Associated function instead of method to avoid "cannot mutate self twice". 
     fn get_range<'a> (value: &Value, clusters: &'a [Range]) -> Option<&'a Range> {
-        clusters.iter().find(|&cluster| cluster.lower != Value::None && *value >= cluster.lower && *value <= cluster.upper)
+        clusters.iter().find(|&cluster| {
+            cluster.lower != Value::None
+                && if cluster.is_lower_exclusive { *value > cluster.lower } else { *value >= cluster.lower }
+                && if cluster.is_upper_exclusive { *value < cluster.upper } else { *value <= cluster.upper }
+        })
     }
 
     // Associated function instead of method to avoid "cannot mutate self twice". 
-    fn recalculate (histogram: &mut Histogram, mapping: &Mapping, name: &String, value: &Value) {
-        if *value == Value::None {
+    fn recalculate (histogram: &mut Histogram, mapping: &Mapping, rules: (OtherValues, MissingValues), name: &String, is_numeric: bool, value: &Value) {
+        let cluster = if *value == Value::None {
             histogram.missing += 1;
+            None
         } else {
-            *histogram.density.entry(Self::bit_name(mapping, name, value)).or_insert(0) += 1;
+            let cluster = Self::cluster_of(mapping, name, is_numeric, value);
+            if cluster.is_none() {
+                histogram.others += 1;
+            }
+            cluster
+        };
+        if let Some(bit) = Self::bit_name(mapping, rules, name, value, cluster) {
+            *histogram.density.entry(bit).or_insert(0) += 1;
         }
-        if  histogram.minimum == Value::None || histogram.minimum > *value {
+        // Numbers take precedence over strings kept in numeric variables, as they can not be compared.
+        let replaces = |current: &Value| matches!((current, value), (Value::String {..}, Value::Number {..}));
+        if  histogram.minimum == Value::None || replaces(&histogram.minimum) || histogram.minimum > *value {
             histogram.minimum = value.clone();
         }
-        if  histogram.maximum == Value::None || histogram.maximum < *value {
+        if  histogram.maximum == Value::None || replaces(&histogram.maximum) || histogram.maximum < *value {
             histogram.maximum = value.clone();
         }
     }
 
 }
 
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn numeric (values: &[&str]) -> Variable {
+        let mut variable = Variable::new("X");
+        for value in values {
+            variable.add_value(value);
+        }
+        variable.as_numbers();
+        variable
+    }
+
+    #[test]
+    fn quantiles_never_split_equal_values () {
+        let variable = numeric(&["1", "1", "1", "1", "1", "2", "3", "3", "3", "3", "3", "4", "5", "5", "5", "5", "5"]);
+        for count in 2..=6 {
+            let ranges = variable.quantiles(count);
+            assert!(!ranges.is_empty() && ranges.len() <= count);
+            assert!(ranges.windows(2).all(|pair| pair[0].upper < pair[1].lower));
+            assert!(variable.values.iter().all(|v| ranges.iter().filter(|r| r.lower <= *v && *v <= r.upper).count() == 1));
+        }
+    }
+}
@@ -1,2 +1,3 @@
+pub mod chart;
 pub mod errorfield;
 pub mod switch;
\ No newline at end of file
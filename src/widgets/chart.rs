@@ -0,0 +1,163 @@
+/*
+
+A small bar chart, painted as a histogram when the bars cover a range of numbers. Vertical lines
+mark values in that range (such as cluster boundaries). Hovering a bar shows its label and count.
+
+If editable, lines can be dragged to another value, a click adds a line and a double click on a
+line removes it, as the hover text then also tells. The chart does not change its lines, it only
+reports the edit.
+
+*/
+
+use eframe::egui;
+use eframe::egui::Widget;
+
+const HEIGHT: f32 = 64.0;
+const GRAB:   f32 = 4.0; // Distance in points from a line within which it can be dragged or removed.
+const HINT:   &str = "Drag a line to move it, click to add one or double click to remove one.";
+
+#[derive(Clone, Copy)]
+pub enum Edit
+{
+    Move { from: f32, to: f32 }, // Line was dragged to another value.
+    Add { at: f32 },             // A new line was added by a click.
+    Remove { at: f32 }           // Line was removed by a double click.
+}
+
+pub struct Chart<'a>
+{
+    bars: Vec<(String, usize)>,        // Label and count of each bar, left to right.
+    range: Option<(f32, f32)>,         // Values at left and right edge, if bars are bins of equal width.
+    lines: Vec<f32>,                   // Values to mark with vertical lines (if range).
+    edit: Option<&'a mut Option<Edit>> // Edit of lines by the user, if editable.
+}
+
+impl<'a> Chart<'a>
+{
+    // Bar chart of categories.
+    pub const fn new (bars: Vec<(String, usize)>) -> Self {
+        Self {
+            bars,
+            range: None,
+            lines: Vec::new(),
+            edit: None
+        }
+    }
+
+    // Histogram of bins of equal width between minimum and maximum, labelled by their lower and upper value.
+    #[allow(clippy::cast_precision_loss)] // Number of bins is far below the precision of f32.
+    pub fn histogram (counts: &[usize], minimum: f32, maximum: f32) -> Self {
+        let width = (maximum - minimum) / counts.len().max(1) as f32;
+        let bars = counts.iter().enumerate().map(|(i, c)| {
+            let lower = minimum + width * i as f32;
+            (format!("{lower} to {}", lower + width), *c)
+        }).collect();
+        Self {
+            bars,
+            range: Some((minimum, maximum)),
+            lines: Vec::new(),
+            edit: None
+        }
+    }
+
+    pub fn lines (mut self, lines: Vec<f32>) -> Self {
+        self.lines = lines;
+        self
+    }
+
+    // Lines can be edited (if range), edit is set when the user has done so.
+    pub fn editable (mut self, edit: &'a mut Option<Edit>) -> Self {
+        self.edit = Some(edit);
+        self
+    }
+
+    // Value rounded to about three significant digits of the range, to keep expressions readable.
+    fn round (value: f32, minimum: f32, maximum: f32) -> f32 {
+        #[allow(clippy::cast_possible_truncation)] // Logarithm of an f32 is far within range of i32.
+        let digits = 2 - (maximum - minimum).log10().floor() as i32;
+        let scale = 10f32.powi(digits.abs());
+        if digits > 0 { (value * scale).round() / scale } else { (value / scale).round() * scale }
+    }
+
+    // Label and count of the hovered bar, if any, and how to edit lines (if editable), in one hover text.
+    fn hover (response: egui::Response, bars: &[(String, usize)], hovered: Option<usize>, editable: bool) -> egui::Response {
+        let hint = if editable { format!("\n{HINT}") } else { String::new() };
+        if let Some(index) = hovered && let Some((label, count)) = bars.get(index) {
+            return response.on_hover_text(format!("{label}: {count}{hint}"))
+        }
+        response
+    }
+}
+
+impl Widget for Chart<'_>
+{
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Counts are far below the precision of f32, pointer is within area.
+    fn ui (self, ui: &mut egui::Ui) -> egui::Response {
+        let size = egui::vec2(ui.available_width(), HEIGHT);
+        let editable = self.edit.is_some() && self.range.is_some_and(|(minimum, maximum)| maximum > minimum);
+        let sense = if editable { egui::Sense::click_and_drag() } else { egui::Sense::hover() };
+        let (area, response) = ui.allocate_exact_size(size, sense);
+        if self.bars.is_empty() || !ui.is_rect_visible(area) {
+            return response
+        }
+        let visuals = ui.style().visuals.clone();
+        let painter = ui.painter_at(area);
+        let highest = self.bars.iter().map(|b| b.1).max().unwrap_or(0).max(1) as f32;
+        let width = area.width() / self.bars.len() as f32;
+        let gap = if width > 4.0 { 1.0 } else { 0.0 };
+        let hovered = response.hover_pos().map(|p| ((p.x - area.left()) / width) as usize);
+        for (index, (_, count)) in self.bars.iter().enumerate() {
+            let left = area.left() + width * index as f32;
+            let top = area.bottom() - area.height() * *count as f32 / highest;
+            let bar = egui::Rect::from_min_max(egui::pos2(left + gap, top), egui::pos2(left + width - gap, area.bottom()));
+            let color = if hovered == Some(index) { visuals.selection.bg_fill } else { visuals.widgets.inactive.bg_fill };
+            painter.rect_filled(bar, 0.0, color);
+        }
+        painter.hline(area.x_range(), area.bottom(), visuals.widgets.noninteractive.bg_stroke);
+        let Some((minimum, maximum)) = self.range.filter(|(minimum, maximum)| maximum > minimum) else {
+            return Self::hover(response, &self.bars, hovered, false)
+        };
+        let x_of = |value: f32| area.left() + area.width() * (value - minimum) / (maximum - minimum);
+        let value_of = |x: f32| Self::round(minimum + (maximum - minimum) * (x - area.left()) / area.width(), minimum, maximum);
+        let near = |x: f32| self.lines.iter().copied().find(|l| (x_of(*l) - x).abs() <= GRAB);
+        // Line being dragged is kept in memory, as the chart is rebuilt every frame.
+        let dragged = ui.data(|d| d.get_temp::<f32>(response.id));
+        let stroke = egui::Stroke::new(1.5, visuals.warn_fg_color);
+        for line in self.lines.iter().filter(|l| (minimum..=maximum).contains(*l) && Some(**l) != dragged) {
+            painter.vline(x_of(*line), area.y_range(), stroke);
+            if editable {
+                painter.circle_filled(egui::pos2(x_of(*line), area.top() + GRAB), GRAB, visuals.warn_fg_color);
+            }
+        }
+        let Some(edit) = self.edit.filter(|_| editable) else {
+            return Self::hover(response, &self.bars, hovered, false)
+        };
+        let position = response.interact_pointer_pos().or(response.hover_pos());
+        if let Some(position) = position && (dragged.is_some() || near(position.x).is_some()) {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+        }
+        if response.drag_started() && let Some(line) = ui.input(|i| i.pointer.press_origin()).and_then(|p| near(p.x)) {
+            ui.data_mut(|d| d.insert_temp(response.id, line));
+        }
+        if let Some(from) = dragged {
+            let x = position.map_or(x_of(from), |p| p.x.clamp(area.left(), area.right()));
+            painter.vline(x, area.y_range(), stroke);
+            painter.text(egui::pos2(x + GRAB, area.top()), egui::Align2::LEFT_TOP, value_of(x).to_string(), egui::TextStyle::Small.resolve(ui.style()), visuals.warn_fg_color);
+            if !response.dragged() {
+                ui.data_mut(|d| d.remove_temp::<f32>(response.id));
+                if response.drag_stopped() {
+                    *edit = Some(Edit::Move { from, to: value_of(x) });
+                }
+            }
+            return response
+        }
+        if let Some(position) = position {
+            if response.double_clicked() && let Some(line) = near(position.x) {
+                *edit = Some(Edit::Remove { at: line });
+            } else if response.clicked() && near(position.x).is_none() {
+                *edit = Some(Edit::Add { at: value_of(position.x) });
+            }
+        }
+        Self::hover(response, &self.bars, hovered, true)
+    }
+}